@@ -1,6 +1,6 @@
 use aoclib::parse;
 use itertools::Itertools;
-use std::{path::Path, str::FromStr};
+use std::{collections::BTreeMap, path::Path, str::FromStr};
 
 struct Report {
     levels: Vec<i32>,
@@ -18,30 +18,125 @@ impl FromStr for Report {
     }
 }
 
-fn diffs_are_safe<Diffs>(diffs: Diffs) -> bool
-where
-    Diffs: IntoIterator<Item = i32>,
-{
-    let mut gt_0 = true;
-    let mut lt_0 = true;
-    let mut in_range = true;
-
-    for item in diffs.into_iter() {
-        gt_0 &= item > 0;
-        lt_0 &= item < 0;
-        in_range &= (1..=3).contains(&item.abs());
+/// Which way the levels of a report must trend.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    /// Either all increasing or all decreasing
+    #[default]
+    Either,
+    Increasing,
+    Decreasing,
+}
+
+/// The rules by which a report is judged safe.
+///
+/// The default policy is the one from the puzzle: levels are strictly monotonic,
+/// and adjacent levels differ by at least 1 and at most 3.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SafetyPolicy {
+    /// Minimum magnitude of the difference between adjacent levels
+    pub min_step: u32,
+    /// Maximum magnitude of the difference between adjacent levels
+    pub max_step: u32,
+    /// When set, adjacent levels may be equal.
+    ///
+    /// Plateaus are exempt from the step range and do not affect the trend.
+    pub allow_plateaus: bool,
+    pub trend: Trend,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self {
+            min_step: 1,
+            max_step: 3,
+            allow_plateaus: false,
+            trend: Trend::Either,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// Load a policy from a TOML file.
+    ///
+    /// Unspecified fields take their default values.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let data = std::fs::read_to_string(path)?;
+        let policy = toml::from_str::<Self>(&data)?;
+        policy.validate()?;
+        Ok(policy)
     }
 
-    (gt_0 || lt_0) && in_range
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.min_step > self.max_step {
+            return Err(Error::InvalidPolicy(format!(
+                "min step ({}) exceeds max step ({})",
+                self.min_step, self.max_step
+            )));
+        }
+        Ok(())
+    }
+
+    fn diffs_are_safe<Diffs>(&self, diffs: Diffs) -> bool
+    where
+        Diffs: IntoIterator<Item = i32>,
+    {
+        let mut gt_0 = true;
+        let mut lt_0 = true;
+        let mut in_range = true;
+
+        for item in diffs.into_iter() {
+            if item == 0 && self.allow_plateaus {
+                continue;
+            }
+            gt_0 &= item > 0;
+            lt_0 &= item < 0;
+            in_range &= (self.min_step..=self.max_step).contains(&item.unsigned_abs());
+        }
+
+        let trend_ok = match self.trend {
+            Trend::Either => gt_0 || lt_0,
+            Trend::Increasing => gt_0,
+            Trend::Decreasing => lt_0,
+        };
+
+        trend_ok && in_range
+    }
+
+    /// Whether a single step is acceptable within a report which trends in `direction`.
+    ///
+    /// `direction` must be either `Increasing` or `Decreasing`.
+    fn step_is_safe(&self, diff: i32, direction: Trend) -> bool {
+        if diff == 0 {
+            return self.allow_plateaus;
+        }
+        let direction_ok = match direction {
+            Trend::Increasing => diff > 0,
+            Trend::Decreasing => diff < 0,
+            Trend::Either => unreachable!("a step is checked against a concrete direction"),
+        };
+        direction_ok && (self.min_step..=self.max_step).contains(&diff.unsigned_abs())
+    }
+
+    /// The concrete directions in which a report may trend under this policy.
+    fn directions(&self) -> &'static [Trend] {
+        match self.trend {
+            Trend::Either => &[Trend::Increasing, Trend::Decreasing],
+            Trend::Increasing => &[Trend::Increasing],
+            Trend::Decreasing => &[Trend::Decreasing],
+        }
+    }
 }
 
 impl Report {
-    fn is_safe(&self) -> bool {
-        diffs_are_safe(self.levels.windows(2).map(|w| w[1] - w[0]))
+    fn is_safe(&self, policy: &SafetyPolicy) -> bool {
+        policy.diffs_are_safe(self.levels.windows(2).map(|w| w[1] - w[0]))
     }
 
-    fn is_safe_with_problem_compensator(&self) -> bool {
-        if self.is_safe() {
+    fn is_safe_with_problem_compensator(&self, policy: &SafetyPolicy) -> bool {
+        if self.is_safe(policy) {
             return true;
         }
 
@@ -54,35 +149,114 @@ impl Report {
                 .filter_map(|(idx, d)| (idx != skip_idx).then_some(d))
                 .tuple_windows()
                 .map(|(a, b)| b - a);
-            if diffs_are_safe(skip_iter) {
+            if policy.diffs_are_safe(skip_iter) {
                 return true;
             }
         }
 
         false
     }
+
+    /// Find the smallest set of levels whose removal makes this report safe.
+    ///
+    /// Equivalently, this finds the longest safe subsequence of levels, and returns
+    /// the indices of the levels not in it, in ascending order.
+    fn levels_to_remove(&self, policy: &SafetyPolicy) -> Vec<usize> {
+        let n = self.levels.len();
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // (length, last index, predecessors) of the best subsequence found so far
+        let mut best: Option<(usize, usize, Vec<Option<usize>>)> = None;
+
+        for &direction in policy.directions() {
+            // `length[i]` is the length of the longest safe subsequence ending at `i`;
+            // `predecessor[i]` is the index before `i` in that subsequence.
+            let mut length = vec![1; n];
+            let mut predecessor = vec![None; n];
+
+            for i in 0..n {
+                for j in 0..i {
+                    if length[j] + 1 > length[i]
+                        && policy.step_is_safe(self.levels[i] - self.levels[j], direction)
+                    {
+                        length[i] = length[j] + 1;
+                        predecessor[i] = Some(j);
+                    }
+                }
+            }
+
+            let (last, &len) = length
+                .iter()
+                .enumerate()
+                .max_by_key(|&(idx, len)| (*len, std::cmp::Reverse(idx)))
+                .expect("report is not empty");
+            if best.as_ref().is_none_or(|(best_len, _, _)| len > *best_len) {
+                best = Some((len, last, predecessor));
+            }
+        }
+
+        let (_, last, predecessor) = best.expect("policy permits at least one direction");
+        let mut keep = vec![false; n];
+        let mut cursor = Some(last);
+        while let Some(idx) = cursor {
+            keep[idx] = true;
+            cursor = predecessor[idx];
+        }
+
+        (0..n).filter(|&idx| !keep[idx]).collect()
+    }
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let safes = parse::<Report>(input)?.filter(Report::is_safe).count();
+pub fn part1(input: &Path, policy: &SafetyPolicy) -> Result<(), Error> {
+    let safes = parse::<Report>(input)?
+        .filter(|report| report.is_safe(policy))
+        .count();
     println!("safe reports: {safes}");
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
+pub fn part2(input: &Path, policy: &SafetyPolicy) -> Result<(), Error> {
     let safes = parse::<Report>(input)?
-        .filter(Report::is_safe_with_problem_compensator)
+        .filter(|report| report.is_safe_with_problem_compensator(policy))
         .count();
     println!("safe reports with problem compensator: {safes}");
     Ok(())
 }
 
+pub fn repairs(input: &Path, policy: &SafetyPolicy) -> Result<(), Error> {
+    let mut histogram = BTreeMap::<usize, usize>::new();
+
+    for (line_idx, report) in parse::<Report>(input)?.enumerate() {
+        let to_remove = report.levels_to_remove(policy);
+        *histogram.entry(to_remove.len()).or_default() += 1;
+        if !to_remove.is_empty() {
+            let removed = to_remove
+                .iter()
+                .map(|&idx| format!("{}@{idx}", report.levels[idx]))
+                .join(", ");
+            println!("report {}: remove {removed}", line_idx + 1);
+        }
+    }
+
+    println!("histogram of levels to remove:");
+    for (n_removals, n_reports) in histogram {
+        println!("  {n_removals:>3}: {n_reports}");
+    }
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("invalid input")]
     InvalidInput(#[from] std::num::ParseIntError),
+    #[error("malformed safety policy")]
+    PolicyFormat(#[from] toml::de::Error),
+    #[error("invalid safety policy: {0}")]
+    InvalidPolicy(String),
     #[error("no solution found")]
     NoSolution,
 }
@@ -104,7 +278,111 @@ mod tests {
             let report = Report {
                 levels: inputs.into(),
             };
-            assert_eq!(report.is_safe_with_problem_compensator(), expect);
+            assert_eq!(
+                report.is_safe_with_problem_compensator(&SafetyPolicy::default()),
+                expect
+            );
+        }
+    }
+
+    mod repairs {
+        use crate::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case([7, 6, 4, 2, 1], 0)]
+        #[case([1, 2, 7, 8, 9], 2)]
+        #[case([9, 7, 6, 2, 1], 2)]
+        #[case([1, 3, 2, 4, 5], 1)]
+        #[case([8, 6, 4, 4, 1], 1)]
+        #[case([1, 3, 6, 7, 9], 0)]
+        #[case([5, 1, 2, 3, 4, 0], 2)]
+        #[case([1, 9, 2, 8, 3, 7], 3)]
+        #[case([1], 0)]
+        fn levels_to_remove<const N: usize>(#[case] inputs: [i32; N], #[case] expect: usize) {
+            let report = Report {
+                levels: inputs.into(),
+            };
+            let policy = SafetyPolicy::default();
+            let to_remove = report.levels_to_remove(&policy);
+            assert_eq!(to_remove.len(), expect);
+
+            let remaining = Report {
+                levels: inputs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, level)| (!to_remove.contains(&idx)).then_some(*level))
+                    .collect(),
+            };
+            assert!(remaining.is_safe(&policy));
+        }
+    }
+
+    mod policy {
+        use crate::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::default(SafetyPolicy::default(), [1, 3, 6, 7, 9], true)]
+        #[case::default_plateau(SafetyPolicy::default(), [1, 3, 3, 7, 9], false)]
+        #[case::plateau(
+            SafetyPolicy { allow_plateaus: true, ..Default::default() },
+            [1, 3, 3, 6, 6],
+            true
+        )]
+        #[case::plateau_big_step(
+            SafetyPolicy { allow_plateaus: true, ..Default::default() },
+            [1, 3, 3, 7, 7],
+            false
+        )]
+        #[case::wide_steps(
+            SafetyPolicy { min_step: 2, max_step: 5, ..Default::default() },
+            [9, 7, 2, 0],
+            true
+        )]
+        #[case::wide_steps_too_small(
+            SafetyPolicy { min_step: 2, max_step: 5, ..Default::default() },
+            [9, 8, 3, 0],
+            false
+        )]
+        #[case::increasing(
+            SafetyPolicy { trend: Trend::Increasing, ..Default::default() },
+            [7, 6, 4, 2, 1],
+            false
+        )]
+        #[case::decreasing(
+            SafetyPolicy { trend: Trend::Decreasing, ..Default::default() },
+            [7, 6, 4, 2, 1],
+            true
+        )]
+        fn is_safe<const N: usize>(
+            #[case] policy: SafetyPolicy,
+            #[case] inputs: [i32; N],
+            #[case] expect: bool,
+        ) {
+            let report = Report {
+                levels: inputs.into(),
+            };
+            assert_eq!(report.is_safe(&policy), expect);
+        }
+
+        #[test]
+        fn load_partial() {
+            let policy = toml::from_str::<SafetyPolicy>(
+                r#"
+                max_step = 4
+                trend = "increasing"
+                "#,
+            )
+            .unwrap();
+            assert_eq!(
+                policy,
+                SafetyPolicy {
+                    max_step: 4,
+                    trend: Trend::Increasing,
+                    ..Default::default()
+                }
+            );
         }
     }
 }
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day02::{part1, part2};
+use day02::{part1, part2, repairs, SafetyPolicy, Trend};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,32 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// list the minimal set of levels to remove from each unsafe report, with a histogram
+    #[arg(long)]
+    repairs: bool,
+
+    /// load the safety policy from this TOML file
+    ///
+    /// flags below override values from the file
+    #[arg(long)]
+    policy: Option<PathBuf>,
+
+    /// minimum magnitude of the step between adjacent levels
+    #[arg(long)]
+    min_step: Option<u32>,
+
+    /// maximum magnitude of the step between adjacent levels
+    #[arg(long)]
+    max_step: Option<u32>,
+
+    /// permit adjacent levels to be equal
+    #[arg(long)]
+    allow_plateaus: bool,
+
+    /// required trend of the levels
+    #[arg(long, value_enum)]
+    trend: Option<Trend>,
 }
 
 impl RunArgs {
@@ -37,18 +63,41 @@ impl RunArgs {
             Some(ref path) => Ok(path.clone()),
         }
     }
+
+    fn policy(&self) -> Result<SafetyPolicy> {
+        let mut policy = match self.policy {
+            None => SafetyPolicy::default(),
+            Some(ref path) => SafetyPolicy::load(path)?,
+        };
+        if let Some(min_step) = self.min_step {
+            policy.min_step = min_step;
+        }
+        if let Some(max_step) = self.max_step {
+            policy.max_step = max_step;
+        }
+        policy.allow_plateaus |= self.allow_plateaus;
+        if let Some(trend) = self.trend {
+            policy.trend = trend;
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::parse();
     let input_path = args.input()?;
+    let policy = args.policy()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, &policy)?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, &policy)?;
+    }
+    if args.repairs {
+        repairs(&input_path, &policy)?;
     }
     Ok(())
 }
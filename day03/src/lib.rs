@@ -1,71 +1,923 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Read},
+    path::Path,
+};
 
-use regex::{Captures, Regex};
+/// Maximum number of digits in an instruction argument.
+const MAX_ARG_DIGITS: usize = 3;
 
-/// extract a number from a capture. assumes that the capture only captures valid numbers and the number is not optional.
-fn expect_num(capture: &Captures, name: &str) -> u32 {
-    capture
-        .name(name)
-        .expect("this group is non-optional")
-        .as_str()
-        .parse()
-        .expect("this group always captures valid numbers")
+/// Number of bytes read at a time when scanning a stream.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A valid instruction extracted from corrupted memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub name: &'a str,
+    pub args: Vec<u32>,
+    /// Byte offset of the start of the instruction within the input
+    pub offset: usize,
+    /// Length in bytes of the instruction
+    pub len: usize,
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let data = std::fs::read_to_string(input)?;
-    let mul_re = Regex::new(r"mul\((?<a>\d{1,3}),(?<b>\d{1,3})\)")?;
-    let mul_sum = mul_re
-        .captures_iter(&data)
-        .map(|capture| {
-            let a = expect_num(&capture, "a");
-            let b = expect_num(&capture, "b");
-            a * b
+/// Parse the argument list of an instruction.
+///
+/// `input` begins immediately after the opening parenthesis. On success, returns the
+/// arguments and the number of bytes consumed, including the closing parenthesis.
+fn lex_args(input: &[u8], arity: usize) -> Option<(Vec<u32>, usize)> {
+    let mut args = Vec::with_capacity(arity);
+    let mut pos = 0;
+
+    if arity == 0 {
+        return (input.first() == Some(&b')')).then_some((args, 1));
+    }
+
+    for arg_idx in 0..arity {
+        let n_digits = input[pos..]
+            .iter()
+            .take(MAX_ARG_DIGITS + 1)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if !(1..=MAX_ARG_DIGITS).contains(&n_digits) {
+            return None;
+        }
+        let arg = input[pos..pos + n_digits]
+            .iter()
+            .fold(0, |acc, digit| acc * 10 + (digit - b'0') as u32);
+        args.push(arg);
+        pos += n_digits;
+
+        let separator = if arg_idx + 1 == arity { b')' } else { b',' };
+        if input.get(pos) != Some(&separator) {
+            return None;
+        }
+        pos += 1;
+    }
+
+    Some((args, pos))
+}
+
+/// Iterator over the valid instructions in some corrupted memory.
+///
+/// Everything which is not a complete, well-formed instruction known to the interpreter
+/// is skipped.
+pub struct Lexer<'i, 'a> {
+    interpreter: &'i Interpreter,
+    input: &'a [u8],
+    pos: usize,
+    /// Instructions may only begin before this position
+    start_limit: usize,
+    /// Absolute offset of `input[0]`
+    base_offset: usize,
+}
+
+impl<'i> Lexer<'i, '_> {
+    /// Attempt to lex an instruction beginning exactly at `self.pos`.
+    fn lex_at_pos(&self) -> Option<(Token<'i>, usize)> {
+        let rest = &self.input[self.pos..];
+        // instructions are sorted longest name first, so `don't` gets a chance before `do`
+        self.interpreter.instructions.iter().find_map(|def| {
+            let args = rest.strip_prefix(def.name.as_bytes())?.strip_prefix(b"(")?;
+            let (args, consumed) = lex_args(args, def.arity)?;
+            let len = def.name.len() + 1 + consumed;
+            let token = Token {
+                name: &def.name,
+                args,
+                offset: self.base_offset + self.pos,
+                len,
+            };
+            Some((token, len))
         })
-        .sum::<u32>();
+    }
+}
 
-    println!("sum of multiplications: {mul_sum}");
-    Ok(())
+impl<'i> Iterator for Lexer<'i, '_> {
+    type Item = Token<'i>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.start_limit {
+            match self.lex_at_pos() {
+                Some((token, len)) => {
+                    self.pos += len;
+                    return Some(token);
+                }
+                None => self.pos += 1,
+            }
+        }
+        None
+    }
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
-    let data = std::fs::read_to_string(input)?;
-    let inst_re = Regex::new(
-        r"(?<mul_instr>mul)\((?<a>\d{1,3}),(?<b>\d{1,3})\)|((?<enable_instr>do(n't)?)\(\))",
-    )?;
+/// State of the machine on which instructions execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Machine {
+    pub enabled: bool,
+    pub sum: u64,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sum: 0,
+        }
+    }
+}
 
-    let mut enabled = true;
-    let mut mul_sum = 0;
+/// Behavior of an instruction: update the machine given the instruction's arguments.
+///
+/// The lexer guarantees that the argument count matches the instruction's registered arity.
+pub type Handler = fn(&mut Machine, &[u32]);
 
-    for capture in inst_re.captures_iter(&data) {
-        match (capture.name("mul_instr"), capture.name("enable_instr")) {
-            (Some(_), None) => {
-                if enabled {
-                    let a = expect_num(&capture, "a");
-                    let b = expect_num(&capture, "b");
+struct InstructionDef {
+    name: String,
+    arity: usize,
+    handler: Handler,
+}
+
+/// Table of known instructions, and the means to execute them.
+#[derive(Default)]
+pub struct Interpreter {
+    /// Always sorted by descending name length
+    instructions: Vec<InstructionDef>,
+}
 
-                    mul_sum += a * b;
+impl Interpreter {
+    /// Register an instruction, replacing any existing instruction of the same name.
+    pub fn register(mut self, name: impl Into<String>, arity: usize, handler: Handler) -> Self {
+        let name = name.into();
+        self.instructions.retain(|def| def.name != name);
+        self.instructions.push(InstructionDef {
+            name,
+            arity,
+            handler,
+        });
+        self.instructions
+            .sort_by_key(|def| std::cmp::Reverse(def.name.len()));
+        self
+    }
+
+    /// Interpreter in which every `mul` counts.
+    pub fn part1() -> Self {
+        Self::default().register("mul", 2, |machine, args| {
+            machine.sum += u64::from(args[0]) * u64::from(args[1]);
+        })
+    }
+
+    /// Interpreter in which `do` and `don't` enable and disable `mul`.
+    pub fn part2() -> Self {
+        Self::default()
+            .register("mul", 2, |machine, args| {
+                if machine.enabled {
+                    machine.sum += u64::from(args[0]) * u64::from(args[1]);
                 }
+            })
+            .register("do", 0, |machine, _| machine.enabled = true)
+            .register("don't", 0, |machine, _| machine.enabled = false)
+    }
+
+    /// Extract the valid instructions from `input`.
+    pub fn lex<'a>(&self, input: &'a [u8]) -> Lexer<'_, 'a> {
+        Lexer {
+            interpreter: self,
+            input,
+            pos: 0,
+            start_limit: input.len(),
+            base_offset: 0,
+        }
+    }
+
+    /// Length in bytes of the longest possible instruction.
+    fn max_instruction_len(&self) -> usize {
+        self.instructions
+            .iter()
+            .map(|def| {
+                let args_len = match def.arity {
+                    0 => 1,
+                    arity => arity * (MAX_ARG_DIGITS + 1),
+                };
+                def.name.len() + 1 + args_len
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Extract the valid instructions from `reader`, reading `chunk_size` bytes at a time.
+    ///
+    /// Memory use is bounded by the chunk size, not the size of the input. Instructions which
+    /// span a chunk boundary are found normally, and token offsets are relative to the start
+    /// of the stream.
+    pub fn scan<'i, R: Read>(
+        &'i self,
+        mut reader: R,
+        chunk_size: usize,
+        mut on_token: impl FnMut(Token<'i>),
+    ) -> io::Result<()> {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let max_len = self.max_instruction_len();
+        let mut chunk = vec![0; chunk_size];
+        // unconsumed tail of the previous chunk, followed by the current chunk
+        let mut buffer = Vec::with_capacity(chunk_size + max_len);
+        let mut buffer_offset = 0;
+
+        loop {
+            let n = match reader.read(&mut chunk) {
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            let eof = n == 0;
+            buffer.extend_from_slice(&chunk[..n]);
+
+            // Until the end of the stream, only lex positions at which the longest instruction
+            // would fit entirely in the buffer. Later positions might begin an instruction which
+            // is completed by the next chunk, so they are carried over.
+            let start_limit = if eof {
+                buffer.len()
+            } else {
+                // an interpreter without instructions has `max_len` 0, so clamp to the buffer
+                (buffer.len() + 1).saturating_sub(max_len).min(buffer.len())
+            };
+            let mut lexer = Lexer {
+                interpreter: self,
+                input: &buffer,
+                pos: 0,
+                start_limit,
+                base_offset: buffer_offset,
+            };
+            for token in lexer.by_ref() {
+                on_token(token);
+            }
+
+            if eof {
+                return Ok(());
+            }
+            let resume = lexer.pos;
+            buffer.drain(..resume);
+            buffer_offset += resume;
+        }
+    }
+
+    /// Whether an instruction of this name is registered.
+    fn recognizes(&self, name: &str) -> bool {
+        self.instructions.iter().any(|def| def.name == name)
+    }
+
+    /// Execute a single instruction.
+    pub fn execute(&self, machine: &mut Machine, token: &Token) {
+        let def = self
+            .instructions
+            .iter()
+            .find(|def| def.name == token.name)
+            .expect("tokens are only produced for registered instructions");
+        (def.handler)(machine, &token.args);
+    }
+
+    /// Execute every valid instruction in `input` on a fresh machine.
+    pub fn run(&self, input: &[u8]) -> Machine {
+        let mut machine = Machine::default();
+        for token in self.lex(input) {
+            self.execute(&mut machine, &token);
+        }
+        machine
+    }
+
+    /// Execute every valid instruction in a stream on a fresh machine.
+    ///
+    /// See [`Self::scan`].
+    pub fn run_stream<R: Read>(&self, reader: R, chunk_size: usize) -> io::Result<Machine> {
+        let mut machine = Machine::default();
+        self.scan(reader, chunk_size, |token| {
+            self.execute(&mut machine, &token)
+        })?;
+        Ok(machine)
+    }
+}
+
+/// Open the input for streaming. The path `-` means stdin.
+fn open(input: &Path) -> io::Result<Box<dyn Read>> {
+    if input == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(std::fs::File::open(input)?))
+    }
+}
+
+/// Why a near-miss instruction was not accepted.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rejection {
+    /// Brackets other than parentheses, like `mul[3,7]`
+    WrongDelimiter,
+    /// Whitespace within the instruction, like `mul ( 2 , 4 )`
+    Whitespace,
+    /// An argument with too many digits, like `mul(1234,5)`
+    ArgumentTooLong,
+    /// The wrong number of arguments, like `mul(2)`
+    WrongArity { expected: usize, found: usize },
+    /// An instruction name followed by an argument list which could not be parsed, like `mul(4*`
+    Malformed,
+    /// A well-formed instruction with an unknown name resembling a known one, like `do_not()`
+    UnknownInstruction { similar_to: String },
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::WrongDelimiter => write!(f, "wrong delimiter"),
+            Rejection::Whitespace => write!(f, "whitespace"),
+            Rejection::ArgumentTooLong => {
+                write!(f, "argument longer than {MAX_ARG_DIGITS} digits")
+            }
+            Rejection::WrongArity { expected, found } => {
+                write!(f, "expected {expected} arguments; found {found}")
+            }
+            Rejection::Malformed => write!(f, "malformed arguments"),
+            Rejection::UnknownInstruction { similar_to } => {
+                write!(f, "unknown instruction (similar to `{similar_to}`)")
             }
-            (None, Some(instr)) => match instr.as_str() {
-                "do" => enabled = true,
-                "don't" => enabled = false,
-                _ => unreachable!("instruction re doesn't match anything else"),
-            },
-            _ => unreachable!("instruction re should always match exactly mul or instr"),
         }
     }
+}
+
+/// A near-miss instruction which the lexer skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Byte offset of the start of the near-miss within the input
+    pub offset: usize,
+    pub text: String,
+    pub rejection: Rejection,
+}
+
+/// Bound on the length of the argument list of a near-miss, so that runaway garbage is not examined.
+const MAX_NEAR_MISS_ARGS_LEN: usize = 32;
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'\''
+}
+
+/// Leniently parse an argument list which might follow an instruction name.
+///
+/// Returns the number of bytes examined and what was wrong with them, if anything.
+/// Returns `None` if this does not look like an argument list at all.
+fn lenient_args(input: &[u8], arity: usize) -> Option<(usize, Option<Rejection>)> {
+    let input = &input[..input.len().min(MAX_NEAR_MISS_ARGS_LEN)];
+    let mut pos = 0;
+    let mut whitespace = false;
+    let mut wrong_delimiter = false;
+    let mut too_long = false;
+    let mut n_args = 0;
+
+    let mut skip_whitespace = |pos: &mut usize| {
+        while input.get(*pos).is_some_and(u8::is_ascii_whitespace) {
+            whitespace = true;
+            *pos += 1;
+        }
+    };
+    let is_close = |b: u8| matches!(b, b')' | b']' | b'}' | b'>');
+
+    skip_whitespace(&mut pos);
+    match input.get(pos)? {
+        b'(' => {}
+        b'[' | b'{' | b'<' => wrong_delimiter = true,
+        _ => return None,
+    }
+    pos += 1;
+
+    skip_whitespace(&mut pos);
+    let close = loop {
+        match input.get(pos) {
+            Some(&b) if is_close(b) && n_args == 0 => break b,
+            Some(b) if b.is_ascii_digit() => {}
+            _ => return Some((pos, Some(Rejection::Malformed))),
+        }
+        let n_digits = input[pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        too_long |= n_digits > MAX_ARG_DIGITS;
+        n_args += 1;
+        pos += n_digits;
+
+        skip_whitespace(&mut pos);
+        match input.get(pos) {
+            Some(b',') => {
+                pos += 1;
+                skip_whitespace(&mut pos);
+            }
+            Some(&b) if is_close(b) => break b,
+            _ => return Some((pos, Some(Rejection::Malformed))),
+        }
+    };
+    wrong_delimiter |= close != b')';
+    pos += 1;
+
+    let rejection = if wrong_delimiter {
+        Some(Rejection::WrongDelimiter)
+    } else if whitespace {
+        Some(Rejection::Whitespace)
+    } else if too_long {
+        Some(Rejection::ArgumentTooLong)
+    } else if n_args != arity {
+        Some(Rejection::WrongArity {
+            expected: arity,
+            found: n_args,
+        })
+    } else {
+        None
+    };
+    Some((pos, rejection))
+}
+
+/// Levenshtein distance between two byte strings.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut current);
+    }
+    prev[b.len()]
+}
+
+impl Interpreter {
+    /// Find the near-miss instructions in `input`: things which look like instructions
+    /// but which the lexer rejects.
+    pub fn diagnose(&self, input: &[u8]) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.diagnose_stream(
+            input,
+            input.len().max(1),
+            |_| {},
+            |diagnostic| diagnostics.push(diagnostic),
+        )
+        .expect("reading from a slice cannot fail");
+        diagnostics
+    }
+
+    /// Find the near-miss instructions in `reader`, reading `chunk_size` bytes at a time.
+    ///
+    /// The valid instructions found along the way are passed to `on_token`, so the stream is
+    /// read only once. As with [`Self::scan`], memory use is bounded by the chunk size, and
+    /// offsets are relative to the start of the stream.
+    pub fn diagnose_stream<'i, R: Read>(
+        &'i self,
+        mut reader: R,
+        chunk_size: usize,
+        mut on_token: impl FnMut(Token<'i>),
+        mut on_diagnostic: impl FnMut(Diagnostic),
+    ) -> io::Result<()> {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let max_len = self.max_instruction_len();
+        let lookahead = self.max_near_miss_len();
+        let mut chunk = vec![0; chunk_size];
+        // unconsumed tail of the previous chunk, followed by the current chunk
+        let mut buffer = Vec::with_capacity(chunk_size + max_len + lookahead);
+        // positions of the buffer covered by valid tokens, which are never near-misses
+        let mut covered = Vec::with_capacity(buffer.capacity());
+        let mut buffer_offset = 0;
+        // positions within the buffer at which lexing and diagnosis resume
+        let mut lex_pos = 0;
+        let mut diagnose_pos = 0;
+
+        loop {
+            let n = match reader.read(&mut chunk) {
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            let eof = n == 0;
+            buffer.extend_from_slice(&chunk[..n]);
+            covered.resize(buffer.len(), false);
+
+            let start_limit = if eof {
+                buffer.len()
+            } else {
+                (buffer.len() + 1).saturating_sub(max_len).min(buffer.len())
+            };
+            let mut lexer = Lexer {
+                interpreter: self,
+                input: &buffer,
+                pos: lex_pos,
+                start_limit,
+                base_offset: buffer_offset,
+            };
+            for token in lexer.by_ref() {
+                let start = token.offset - buffer_offset;
+                covered[start..start + token.len].fill(true);
+                on_token(token);
+            }
+            lex_pos = lexer.pos;
+
+            // Coverage is final only behind the lexer, and a near-miss may extend `lookahead`
+            // bytes past its start, so until the end of the stream, diagnosis trails the lexer.
+            let diagnose_limit = if eof {
+                buffer.len()
+            } else {
+                lex_pos.saturating_sub(lookahead)
+            };
+            while diagnose_pos < diagnose_limit {
+                if covered[diagnose_pos] {
+                    diagnose_pos += 1;
+                    continue;
+                }
+                match self
+                    .diagnose_known_name(&buffer, diagnose_pos)
+                    .or_else(|| self.diagnose_unknown_name(&buffer, diagnose_pos, &covered))
+                {
+                    Some((len, rejection)) => {
+                        on_diagnostic(Diagnostic {
+                            offset: buffer_offset + diagnose_pos,
+                            text: String::from_utf8_lossy(
+                                &buffer[diagnose_pos..diagnose_pos + len],
+                            )
+                            .into_owned(),
+                            rejection,
+                        });
+                        diagnose_pos += len;
+                    }
+                    None => diagnose_pos += 1,
+                }
+            }
+
+            if eof {
+                return Ok(());
+            }
+            // keep the byte before the next diagnosis, which decides whether a name starts there
+            let resume = lex_pos.min(diagnose_pos.saturating_sub(1));
+            buffer.drain(..resume);
+            covered.drain(..resume);
+            buffer_offset += resume;
+            lex_pos -= resume;
+            diagnose_pos -= resume;
+        }
+    }
+
+    /// Length in bytes of the longest name which is similar enough to a known name to be
+    /// reported as a near-miss.
+    fn max_near_miss_name_len(&self) -> usize {
+        self.instructions
+            .iter()
+            .map(|def| def.name.len() + (def.name.len() / 2).max(1))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Number of bytes which diagnosing a near-miss may examine, starting from its first byte.
+    fn max_near_miss_len(&self) -> usize {
+        self.max_near_miss_name_len() + MAX_NEAR_MISS_ARGS_LEN
+    }
+
+    /// A known instruction name, followed by something resembling an argument list.
+    fn diagnose_known_name(&self, input: &[u8], pos: usize) -> Option<(usize, Rejection)> {
+        let rest = &input[pos..];
+        self.instructions.iter().find_map(|def| {
+            let args = rest.strip_prefix(def.name.as_bytes())?;
+            let (args_len, rejection) = lenient_args(args, def.arity)?;
+            Some((def.name.len() + args_len, rejection?))
+        })
+    }
+
+    /// A well-formed instruction whose name is not known, but resembles a known name.
+    fn diagnose_unknown_name(
+        &self,
+        input: &[u8],
+        pos: usize,
+        covered: &[bool],
+    ) -> Option<(usize, Rejection)> {
+        if pos > 0 && is_ident_byte(input[pos - 1]) {
+            return None;
+        }
+        // longer names are too far from every known name to resemble one
+        let max_name_len = self.max_near_miss_name_len();
+        let name_len = input[pos..]
+            .iter()
+            .take(max_name_len + 1)
+            .take_while(|&&b| is_ident_byte(b))
+            .count();
+        if name_len == 0 || name_len > max_name_len {
+            return None;
+        }
+        let name = &input[pos..pos + name_len];
+        let (args_len, rejection) = lenient_args(&input[pos + name_len..], 0)?;
+        let len = name_len + args_len;
+        // a known instruction may end a longer identifier, like `undo()`; that is not a near-miss
+        let well_formed = matches!(rejection, None | Some(Rejection::WrongArity { .. }));
+        if !well_formed || covered[pos..pos + len].iter().any(|&c| c) {
+            return None;
+        }
+
+        let (distance, similar) = self
+            .instructions
+            .iter()
+            .map(|def| (edit_distance(name, def.name.as_bytes()), &def.name))
+            .min()?;
+        (distance <= (similar.len() / 2).max(1)).then(|| {
+            let rejection = Rejection::UnknownInstruction {
+                similar_to: similar.clone(),
+            };
+            (len, rejection)
+        })
+    }
+}
+
+pub fn diagnostics(input: &Path) -> Result<(), Error> {
+    let interpreter = Interpreter::part2();
+
+    let mut n_diagnostics = 0;
+    let mut by_reason = BTreeMap::<_, usize>::new();
+    let mut machine = Machine::default();
+    let mut muls = 0;
+    let mut disabled_muls = 0;
+    interpreter.diagnose_stream(
+        open(input)?,
+        DEFAULT_CHUNK_SIZE,
+        |token| {
+            if token.name == "mul" {
+                muls += 1;
+                if !machine.enabled {
+                    disabled_muls += 1;
+                }
+            }
+            interpreter.execute(&mut machine, &token);
+        },
+        |diagnostic| {
+            println!(
+                "{:>8}: {:?}: {}",
+                diagnostic.offset, diagnostic.text, diagnostic.rejection
+            );
+            n_diagnostics += 1;
+            *by_reason.entry(diagnostic.rejection).or_default() += 1;
+        },
+    )?;
+
+    println!("{n_diagnostics} near-miss instructions");
+    for (rejection, count) in by_reason {
+        println!("  {count:>6}: {rejection}");
+    }
+    println!("{disabled_muls} of {muls} mul instructions disabled by don't()");
+
+    Ok(())
+}
+
+pub fn part1(input: &Path) -> Result<(), Error> {
+    let mul_sum = Interpreter::part1()
+        .run_stream(open(input)?, DEFAULT_CHUNK_SIZE)?
+        .sum;
+
+    println!("sum of multiplications: {mul_sum}");
+    Ok(())
+}
+
+pub fn part2(input: &Path) -> Result<(), Error> {
+    let mul_sum = Interpreter::part2()
+        .run_stream(open(input)?, DEFAULT_CHUNK_SIZE)?
+        .sum;
 
     println!("sum of multiplication (part2): {mul_sum}");
     Ok(())
 }
 
+/// Execute every valid instruction in a stream on fresh part 1 and part 2 machines, reading
+/// the stream only once.
+fn run_both<R: Read>(reader: R, chunk_size: usize) -> io::Result<(Machine, Machine)> {
+    let part1 = Interpreter::part1();
+    let part2 = Interpreter::part2();
+    let mut machine1 = Machine::default();
+    let mut machine2 = Machine::default();
+    part2.scan(reader, chunk_size, |token| {
+        if part1.recognizes(token.name) {
+            part1.execute(&mut machine1, &token);
+        }
+        part2.execute(&mut machine2, &token);
+    })?;
+    Ok((machine1, machine2))
+}
+
+/// Run both parts in a single pass over the input, so that a stream such as stdin is only
+/// read once.
+pub fn both_parts(input: &Path) -> Result<(), Error> {
+    let (machine1, machine2) = run_both(open(input)?, DEFAULT_CHUNK_SIZE)?;
+
+    println!("sum of multiplications: {}", machine1.sum);
+    println!("sum of multiplication (part2): {}", machine2.sum);
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error(transparent)]
-    Re(#[from] regex::Error),
     #[error("no solution found")]
     NoSolution,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const EXAMPLE_1: &[u8] =
+        b"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const EXAMPLE_2: &[u8] =
+        b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn lex_part1() {
+        let interpreter = Interpreter::part1();
+        let tokens = interpreter
+            .lex(EXAMPLE_1)
+            .map(|token| (token.offset, token.args))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                (1, vec![2, 4]),
+                (29, vec![5, 5]),
+                (53, vec![11, 8]),
+                (62, vec![8, 5]),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_part2() {
+        let interpreter = Interpreter::part2();
+        let names = interpreter
+            .lex(EXAMPLE_2)
+            .map(|token| token.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["mul", "don't", "mul", "mul", "do", "mul"]);
+    }
+
+    #[test]
+    fn run_examples() {
+        assert_eq!(Interpreter::part1().run(EXAMPLE_1).sum, 161);
+        assert_eq!(Interpreter::part2().run(EXAMPLE_2).sum, 48);
+    }
+
+    #[test]
+    fn stream_matches_whole() {
+        let interpreter = Interpreter::part2().register("add", 3, |machine, args| {
+            machine.sum += args.iter().copied().map(u64::from).sum::<u64>();
+        });
+        let input = [EXAMPLE_1, EXAMPLE_2, b"add(100,20,3)don't()add(1,1,1)do()"].concat();
+        let whole = interpreter.lex(&input).collect::<Vec<_>>();
+
+        for chunk_size in 1..=input.len() + 1 {
+            let mut streamed = Vec::new();
+            interpreter
+                .scan(input.as_slice(), chunk_size, |token| streamed.push(token))
+                .unwrap();
+            assert_eq!(streamed, whole, "chunk size {chunk_size}");
+            assert_eq!(
+                interpreter
+                    .run_stream(input.as_slice(), chunk_size)
+                    .unwrap(),
+                interpreter.run(&input),
+                "chunk size {chunk_size}"
+            );
+        }
+    }
+
+    #[test]
+    fn stream_sum_exceeds_u32() {
+        let input = b"mul(999,999)".repeat(5000);
+        let machine = Interpreter::part1()
+            .run_stream(input.as_slice(), 64)
+            .unwrap();
+        assert_eq!(machine.sum, 999 * 999 * 5000);
+        assert!(machine.sum > u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn stream_without_instructions() {
+        for chunk_size in [1, 4, 64] {
+            let machine = Interpreter::default()
+                .run_stream(EXAMPLE_1, chunk_size)
+                .unwrap();
+            assert_eq!(machine, Machine::default(), "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn both_parts_in_one_pass() {
+        for (input, part1, part2) in [(EXAMPLE_1, 161, 161), (EXAMPLE_2, 161, 48)] {
+            for chunk_size in [1, 7, input.len()] {
+                let (machine1, machine2) = run_both(input, chunk_size).unwrap();
+                assert_eq!(machine1.sum, part1, "chunk size {chunk_size}");
+                assert_eq!(machine2.sum, part2, "chunk size {chunk_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        let interpreter = Interpreter::part2();
+        for input in [
+            &b"mul(1234,5)"[..],
+            b"mul(1,2,3)",
+            b"mul(,2)",
+            b"mul ( 2 , 4 )",
+            b"do(1)",
+            b"mul(4*",
+        ] {
+            assert_eq!(interpreter.lex(input).count(), 0, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn diagnose() {
+        let interpreter = Interpreter::part2();
+        for (input, expect_text, expect_rejection) in [
+            (
+                &b"xmul(1234,5)"[..],
+                "mul(1234,5)",
+                Rejection::ArgumentTooLong,
+            ),
+            (b"!mul[3,7]!", "mul[3,7]", Rejection::WrongDelimiter),
+            (b"mul(32,64]then", "mul(32,64]", Rejection::WrongDelimiter),
+            (b"+mul ( 2 , 4 )", "mul ( 2 , 4 )", Rejection::Whitespace),
+            (
+                b"mul(2)",
+                "mul(2)",
+                Rejection::WrongArity {
+                    expected: 2,
+                    found: 1,
+                },
+            ),
+            (b"mul(4*", "mul(4", Rejection::Malformed),
+            (
+                b"^do_not()",
+                "do_not()",
+                Rejection::UnknownInstruction {
+                    similar_to: "don't".into(),
+                },
+            ),
+        ] {
+            let diagnostics = interpreter.diagnose(input);
+            assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+            assert_eq!(diagnostics[0].text, expect_text);
+            assert_eq!(diagnostics[0].rejection, expect_rejection);
+        }
+    }
+
+    #[test]
+    fn diagnose_stream_matches_whole() {
+        let interpreter = Interpreter::part2();
+        let input = [
+            EXAMPLE_2,
+            b"xmul(1234,5)!mul[3,7]!mul(32,64]then+mul ( 2 , 4 )mul(2)",
+            b"^do_not()undo()don'tt()mul(4*do()",
+        ]
+        .concat();
+        let whole = interpreter.diagnose(&input);
+        let whole_tokens = interpreter.lex(&input).collect::<Vec<_>>();
+        assert_eq!(whole.len(), 10, "{whole:?}");
+
+        for chunk_size in 1..=input.len() + 1 {
+            let mut tokens = Vec::new();
+            let mut diagnostics = Vec::new();
+            interpreter
+                .diagnose_stream(
+                    input.as_slice(),
+                    chunk_size,
+                    |token| tokens.push(token),
+                    |diagnostic| diagnostics.push(diagnostic),
+                )
+                .unwrap();
+            assert_eq!(diagnostics, whole, "chunk size {chunk_size}");
+            assert_eq!(tokens, whole_tokens, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn diagnose_ignores_valid() {
+        let interpreter = Interpreter::part2();
+        for input in [
+            EXAMPLE_2,
+            b"do_not_mul(5,5)",
+            b"undo()",
+            b"what()select()from(1,2)",
+        ] {
+            let diagnostics = interpreter
+                .diagnose(input)
+                .into_iter()
+                .filter(|diagnostic| diagnostic.rejection != Rejection::WrongDelimiter)
+                .collect::<Vec<_>>();
+            assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        }
+    }
+
+    #[test]
+    fn custom_instruction() {
+        let interpreter = Interpreter::part2().register("add", 3, |machine, args| {
+            machine.sum += args.iter().copied().map(u64::from).sum::<u64>();
+        });
+        assert_eq!(interpreter.run(b"add(1,2,3)mul(2,2)add(1,1)").sum, 10);
+    }
+}
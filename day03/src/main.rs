@@ -1,9 +1,9 @@
 use aoclib::{config::Config, website::get_input};
-use day03::{part1, part2};
+use day03::{both_parts, diagnostics, part1, part2};
 
 use clap::Parser;
-use color_eyre::eyre::Result;
-use std::path::PathBuf;
+use color_eyre::eyre::{bail, Result};
+use std::path::{Path, PathBuf};
 
 const YEAR: u32 = 2024;
 const DAY: u8 = 3;
@@ -12,7 +12,8 @@ const DAY: u8 = 3;
 struct RunArgs {
     /// input file
     ///
-    /// when unset, defaults to `inputs/input-NN.txt`, where `NN` is the current day
+    /// when unset, defaults to `inputs/input-NN.txt`, where `NN` is the current day.
+    /// `-` reads from stdin.
     input: Option<PathBuf>,
 
     /// skip part 1
@@ -22,6 +23,10 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// report near-miss instructions and how many `mul`s were disabled
+    #[arg(long)]
+    diagnostics: bool,
 }
 
 impl RunArgs {
@@ -44,11 +49,19 @@ fn main() -> Result<()> {
     let args = RunArgs::parse();
     let input_path = args.input()?;
 
-    if !args.no_part1 {
-        part1(&input_path)?;
+    let parts = !args.no_part1 || args.part2;
+    if input_path == Path::new("-") && parts && args.diagnostics {
+        bail!("stdin can only be read once; pass --no-part1 and omit --part2 to run --diagnostics");
+    }
+
+    match (!args.no_part1, args.part2) {
+        (true, true) => both_parts(&input_path)?,
+        (true, false) => part1(&input_path)?,
+        (false, true) => part2(&input_path)?,
+        (false, false) => {}
     }
-    if args.part2 {
-        part2(&input_path)?;
+    if args.diagnostics {
+        diagnostics(&input_path)?;
     }
     Ok(())
 }
@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day04::{find_words, Bitboard, WordSearch};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/// A square grid of pseudorandom letters drawn from `XMAS`.
+fn random_grid(size: usize) -> WordSearch {
+    let mut rng = StdRng::seed_from_u64(0x2024_1204);
+    let mut data = String::with_capacity((size + 1) * size);
+    for _ in 0..size {
+        for _ in 0..size {
+            data.push(*b"XMAS".choose(&mut rng).expect("alphabet is not empty") as char);
+        }
+        data.push('\n');
+    }
+    <WordSearch as TryFrom<&str>>::try_from(data.as_str()).expect("generated grid is valid")
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xmas");
+    for size in [140, 1000, 3000] {
+        let grid = random_grid(size);
+
+        group.bench_with_input(BenchmarkId::new("scalar", size), &grid, |b, grid| {
+            b.iter(|| find_words(grid, &["XMAS"]).len())
+        });
+
+        group.bench_with_input(BenchmarkId::new("bitboard", size), &grid, |b, grid| {
+            b.iter(|| Bitboard::new(grid).count_word("XMAS"))
+        });
+
+        let bitboard = Bitboard::new(&grid);
+        group.bench_with_input(
+            BenchmarkId::new("bitboard_prebuilt", size),
+            &bitboard,
+            |b, bitboard| b.iter(|| bitboard.count_word("XMAS")),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, search);
+criterion_main!(benches);
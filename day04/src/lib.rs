@@ -1,7 +1,12 @@
 use aoclib::geometry::{tile::DisplayWidth, MapConversionErr, Point};
-use std::path::Path;
+use rand::{seq::SliceRandom, Rng};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
 
-type WordSearch = aoclib::geometry::map::Map<Char>;
+pub type WordSearch = aoclib::geometry::map::Map<Char>;
 
 #[derive(
     Debug,
@@ -19,66 +24,571 @@ type WordSearch = aoclib::geometry::map::Map<Char>;
     derive_more::Display,
     derive_more::FromStr,
 )]
-struct Char(char);
+pub struct Char(char);
 
 impl DisplayWidth for Char {
     const DISPLAY_WIDTH: usize = 1;
 }
 
-const XMAS: &[u8] = b"XMAS";
+const XMAS: &str = "XMAS";
 
-fn directions() -> impl Iterator<Item = (i32, i32)> {
-    (-1..=1)
-        .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
-        .filter(|(dx, dy)| !(*dx == 0 && *dy == 0))
+/// One of the eight directions in which a word can be laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, derive_more::Display)]
+pub enum Heading {
+    #[display("N")]
+    North,
+    #[display("NE")]
+    NorthEast,
+    #[display("E")]
+    East,
+    #[display("SE")]
+    SouthEast,
+    #[display("S")]
+    South,
+    #[display("SW")]
+    SouthWest,
+    #[display("W")]
+    West,
+    #[display("NW")]
+    NorthWest,
 }
 
-fn is_xmas(grid: &WordSearch, origin: Point, dx: i32, dy: i32) -> bool {
-    let mut idx = 0;
-    for point in grid.project(origin, dx, dy).take(XMAS.len()) {
-        if (*grid[point] as u8) != XMAS[idx] {
+impl Heading {
+    pub const ALL: [Self; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter()
+    }
+
+    /// `(dx, dy)` of a single step in this heading; north is `+y`.
+    pub fn deltas(self) -> (i32, i32) {
+        match self {
+            Self::North => (0, 1),
+            Self::NorthEast => (1, 1),
+            Self::East => (1, 0),
+            Self::SouthEast => (1, -1),
+            Self::South => (0, -1),
+            Self::SouthWest => (-1, -1),
+            Self::West => (-1, 0),
+            Self::NorthWest => (-1, 1),
+        }
+    }
+}
+
+fn is_word(grid: &WordSearch, word: &str, origin: Point, heading: Heading) -> bool {
+    let (dx, dy) = heading.deltas();
+    let mut n_chars = 0;
+    for (point, ch) in grid.project(origin, dx, dy).zip(word.chars()) {
+        if *grid[point] != ch {
             return false;
         }
-        idx += 1;
+        n_chars += 1;
     }
 
-    idx == XMAS.len()
+    n_chars == word.chars().count()
 }
 
-fn is_x_mas(grid: &WordSearch, origin: Point) -> bool {
-    let is_mas = |dx, dy| {
-        let pm = origin + (dx, dy);
-        let ps = origin + (-dx, -dy);
-        *grid[origin] == 'A'
-            && grid.in_bounds(pm)
-            && *grid[pm] == 'M'
-            && grid.in_bounds(ps)
-            && *grid[ps] == 'S'
-    };
+/// An occurrence of a word in a word search.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WordMatch {
+    pub word: String,
+    pub start: Point,
+    pub heading: Heading,
+}
+
+impl WordMatch {
+    /// The cells occupied by this match.
+    pub fn points(&self) -> impl '_ + Iterator<Item = Point> {
+        let (dx, dy) = self.heading.deltas();
+        (0..self.word.chars().count() as i32).map(move |idx| self.start + (dx * idx, dy * idx))
+    }
+}
 
-    let diags = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+/// Find every occurrence of each of `words` in `grid`, in every heading.
+///
+/// Occurrences are distinct when they differ in start or heading, so a palindrome may be found
+/// twice along the same cells. Single-character words are reported only once per cell, heading east.
+pub fn find_words<S: AsRef<str>>(grid: &WordSearch, words: &[S]) -> Vec<WordMatch> {
+    let mut matches = Vec::new();
+    for (origin, _tile) in grid.iter() {
+        for word in words {
+            let word = word.as_ref();
+            for &heading in headings_for(word) {
+                if is_word(grid, word, origin, heading) {
+                    matches.push(WordMatch {
+                        word: word.to_owned(),
+                        start: origin,
+                        heading,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
 
-    let mas_count = diags
-        .into_iter()
-        .filter(|(dx, dy)| is_mas(*dx, *dy))
-        .count();
+/// The headings in which to search for `word`.
+///
+/// Single-character words are searched only heading east, so that each cell matches once.
+fn headings_for(word: &str) -> &'static [Heading] {
+    match word.chars().count() {
+        0 => &[],
+        1 => &[Heading::East],
+        _ => &Heading::ALL,
+    }
+}
 
-    mas_count > 1
+/// A word search encoded as one bitboard per letter.
+///
+/// Each row of a letter's bitboard has bit `x` set when that letter appears in column `x`.
+/// A word is found in all positions of a row at once by shifting and intersecting the rows
+/// of its letters' bitboards, instead of probing cell by cell.
+#[derive(Debug, Clone)]
+pub struct Bitboard {
+    height: usize,
+    /// Number of `u64` words per row
+    row_len: usize,
+    /// Row `y` of a letter's board occupies `board[y * row_len..(y + 1) * row_len]`
+    letters: HashMap<char, Vec<u64>>,
+    /// Bits set for every in-bounds column
+    full_row: Vec<u64>,
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let search_grid = <WordSearch as TryFrom<&Path>>::try_from(input)?;
+impl Bitboard {
+    pub fn new(grid: &WordSearch) -> Self {
+        let width = grid.width();
+        let height = grid.height();
+        let row_len = width.div_ceil(64);
+        let mut letters = HashMap::<char, Vec<u64>>::new();
+        for (point, ch) in grid.iter() {
+            let board = letters
+                .entry(**ch)
+                .or_insert_with(|| vec![0; row_len * height]);
+            let x = point.x as usize;
+            board[point.y as usize * row_len + x / 64] |= 1 << (x % 64);
+        }
+
+        let mut full_row = vec![!0; row_len];
+        let tail_bits = width % 64;
+        if tail_bits > 0 {
+            full_row[row_len - 1] = (1 << tail_bits) - 1;
+        }
+
+        Self {
+            height,
+            row_len,
+            letters,
+            full_row,
+        }
+    }
 
-    let mut count = 0;
+    fn row(&self, letter: char, y: i32) -> Option<&[u64]> {
+        if y < 0 || y as usize >= self.height {
+            return None;
+        }
+        let y = y as usize;
+        self.letters
+            .get(&letter)
+            .map(|board| &board[y * self.row_len..(y + 1) * self.row_len])
+    }
 
-    for (origin, _tile) in search_grid.iter() {
-        for (dx, dy) in directions() {
-            if is_xmas(&search_grid, origin, dx, dy) {
-                count += 1;
+    /// Intersect `acc` with `row` shifted such that bit `x` of the result is bit `x + shift`
+    /// of `row`. Bits shifted in from beyond the row are zero.
+    fn and_shifted(acc: &mut [u64], row: &[u64], shift: i32) {
+        let len = row.len() as isize;
+        let words = (shift.unsigned_abs() / 64) as isize;
+        let bits = shift.unsigned_abs() % 64;
+        let get = |idx: isize| {
+            if (0..len).contains(&idx) {
+                row[idx as usize]
+            } else {
+                0
             }
+        };
+
+        for (idx, word) in acc.iter_mut().enumerate() {
+            let idx = idx as isize;
+            let shifted = if shift >= 0 {
+                let low = get(idx + words) >> bits;
+                let high = if bits == 0 {
+                    0
+                } else {
+                    get(idx + words + 1) << (64 - bits)
+                };
+                low | high
+            } else {
+                let high = get(idx - words) << bits;
+                let low = if bits == 0 {
+                    0
+                } else {
+                    get(idx - words - 1) >> (64 - bits)
+                };
+                high | low
+            };
+            *word &= shifted;
+        }
+    }
+
+    /// Count the occurrences of `word` starting in row `y` and proceeding in `heading`.
+    fn count_in_row(&self, word: &str, y: i32, heading: Heading) -> usize {
+        let (dx, dy) = heading.deltas();
+        let mut acc = self.full_row.clone();
+        for (idx, ch) in word.chars().enumerate() {
+            let idx = idx as i32;
+            let Some(row) = self.row(ch, y + idx * dy) else {
+                return 0;
+            };
+            Self::and_shifted(&mut acc, row, idx * dx);
+        }
+        acc.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Count the occurrences of `word` in every heading, with the same semantics as [`find_words`].
+    ///
+    /// Rows are searched in parallel.
+    pub fn count_word(&self, word: &str) -> usize {
+        headings_for(word)
+            .iter()
+            .map(|&heading| {
+                (0..self.height as i32)
+                    .into_par_iter()
+                    .map(|y| self.count_in_row(word, y, heading))
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+/// Count of matches by word and by heading.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MatchCounts {
+    pub by_word: BTreeMap<String, usize>,
+    pub by_heading: BTreeMap<Heading, usize>,
+}
+
+impl MatchCounts {
+    pub fn new(matches: &[WordMatch]) -> Self {
+        let mut counts = Self::default();
+        for m in matches {
+            *counts.by_word.entry(m.word.clone()).or_default() += 1;
+            *counts.by_heading.entry(m.heading).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// The X-MAS shape from part 2, as a pattern template.
+const X_MAS: &str = "
+M.S
+.A.
+M.S
+";
+
+/// Character which matches any letter in a pattern template.
+pub const DEFAULT_WILDCARD: char = '.';
+
+/// A symmetry of the square: some number of quarter turns, optionally preceded by a reflection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Orientation {
+    /// Quarter turns counterclockwise
+    pub quarter_turns: u8,
+    /// Reflected left-to-right before turning
+    pub reflected: bool,
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rot{}", self.quarter_turns as u32 * 90)?;
+        if self.reflected {
+            write!(f, "+refl")?;
         }
+        Ok(())
     }
+}
+
+impl Orientation {
+    pub fn iter() -> impl Iterator<Item = Self> {
+        [false, true].into_iter().flat_map(|reflected| {
+            (0..4).map(move |quarter_turns| Self {
+                quarter_turns,
+                reflected,
+            })
+        })
+    }
+
+    fn apply(self, (mut x, mut y): (i32, i32)) -> (i32, i32) {
+        if self.reflected {
+            x = -x;
+        }
+        for _ in 0..self.quarter_turns {
+            (x, y) = (-y, x);
+        }
+        (x, y)
+    }
+}
+
+/// A 2d pattern of letters, in which some cells may be wildcards.
+///
+/// Only the non-wildcard cells are stored, as offsets from the pattern's anchor, which is the
+/// bottom left of its bounding box.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Template {
+    /// Sorted, so that equal shapes compare equal
+    cells: Vec<(i32, i32, char)>,
+}
+
+impl Template {
+    /// Parse a template. The first line of `s` is the top of the pattern.
+    ///
+    /// Short rows are padded with wildcards.
+    pub fn parse(s: &str, wildcard: char) -> Result<Self, Error> {
+        let mut cells = Vec::new();
+        for (row, line) in s.lines().enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                if ch != wildcard {
+                    cells.push((col as i32, -(row as i32), ch));
+                }
+            }
+        }
+        if cells.is_empty() {
+            return Err(Error::InvalidTemplate(s.into()));
+        }
+        Ok(Self::normalized(cells))
+    }
+
+    fn normalized(mut cells: Vec<(i32, i32, char)>) -> Self {
+        let min_x = cells.iter().map(|(x, _, _)| *x).min().unwrap_or_default();
+        let min_y = cells.iter().map(|(_, y, _)| *y).min().unwrap_or_default();
+        for (x, y, _) in cells.iter_mut() {
+            *x -= min_x;
+            *y -= min_y;
+        }
+        cells.sort_unstable();
+        Self { cells }
+    }
+
+    fn oriented(&self, orientation: Orientation) -> Self {
+        Self::normalized(
+            self.cells
+                .iter()
+                .map(|&(x, y, ch)| {
+                    let (x, y) = orientation.apply((x, y));
+                    (x, y, ch)
+                })
+                .collect(),
+        )
+    }
+
+    /// Every distinct orientation of this template.
+    ///
+    /// Orientations which produce a shape identical to an earlier one are omitted,
+    /// so that a symmetric template matches each location only once.
+    pub fn orientations(&self) -> Vec<(Orientation, Template)> {
+        let mut orientations = Vec::<(Orientation, Template)>::with_capacity(8);
+        for orientation in Orientation::iter() {
+            let oriented = self.oriented(orientation);
+            if !orientations.iter().any(|(_, t)| *t == oriented) {
+                orientations.push((orientation, oriented));
+            }
+        }
+        orientations
+    }
+
+    fn matches_at(&self, grid: &WordSearch, anchor: Point) -> bool {
+        self.cells.iter().all(|&(dx, dy, ch)| {
+            let point = anchor + (dx, dy);
+            grid.in_bounds(point) && *grid[point] == ch
+        })
+    }
+}
+
+/// Load templates from a file. Templates are separated by blank lines.
+pub fn load_templates(path: &Path, wildcard: char) -> Result<Vec<Template>, Error> {
+    let data = std::fs::read_to_string(path)?;
+    data.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(|block| block.trim_matches('\n'))
+        .filter(|block| !block.is_empty())
+        .map(|block| Template::parse(block, wildcard))
+        .collect()
+}
+
+/// An occurrence of a template in a word search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PatternMatch {
+    /// Index of the matching template
+    pub template: usize,
+    pub orientation: Orientation,
+    /// Bottom left of the bounding box of the oriented template
+    pub anchor: Point,
+}
+
+impl PatternMatch {
+    /// The non-wildcard cells occupied by this match.
+    pub fn points(&self, templates: &[Template]) -> Vec<Point> {
+        templates[self.template]
+            .oriented(self.orientation)
+            .cells
+            .into_iter()
+            .map(|(dx, dy, _ch)| self.anchor + (dx, dy))
+            .collect()
+    }
+}
+
+/// Find every occurrence of any orientation of each template in `grid`.
+pub fn find_patterns(grid: &WordSearch, templates: &[Template]) -> Vec<PatternMatch> {
+    let mut matches = Vec::new();
+    for (template_idx, template) in templates.iter().enumerate() {
+        for (orientation, oriented) in template.orientations() {
+            for (anchor, _tile) in grid.iter() {
+                if oriented.matches_at(grid, anchor) {
+                    matches.push(PatternMatch {
+                        template: template_idx,
+                        orientation,
+                        anchor,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Parameters for generating a word search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    pub width: usize,
+    pub height: usize,
+    /// Permit words to cross each other where they share a letter
+    pub allow_overlap: bool,
+    /// Letters with which to fill the cells not occupied by words.
+    ///
+    /// When empty, uses the letters of the words themselves, which makes for more distractions.
+    pub filler: String,
+}
+
+/// A generated word search, and where its words are hidden.
+#[derive(Debug, Clone)]
+pub struct GeneratedPuzzle {
+    pub grid: WordSearch,
+    pub key: Vec<WordMatch>,
+}
+
+/// Hide `words` in a new word search, each in a random position and heading.
+///
+/// Longer words are placed first, as they are the hardest to fit.
+pub fn generate<S: AsRef<str>>(
+    words: &[S],
+    options: &GeneratorOptions,
+    rng: &mut impl Rng,
+) -> Result<GeneratedPuzzle, Error> {
+    let GeneratorOptions {
+        width,
+        height,
+        allow_overlap,
+        ref filler,
+    } = *options;
+    let in_bounds = |point: Point| {
+        (0..width as i32).contains(&point.x) && (0..height as i32).contains(&point.y)
+    };
+    let index = |point: Point| point.y as usize * width + point.x as usize;
+
+    // row `y` occupies `cells[y * width..(y + 1) * width]`, as in the word search itself
+    let mut cells = vec![None::<char>; width * height];
+    let mut key = Vec::with_capacity(words.len());
+
+    let mut words = words.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+    words.sort_by_key(|word| std::cmp::Reverse(word.chars().count()));
 
+    for word in words {
+        let mut candidates = Vec::new();
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                for &heading in headings_for(word) {
+                    let candidate = WordMatch {
+                        word: word.to_owned(),
+                        start: Point::new(x, y),
+                        heading,
+                    };
+                    let fits = candidate.points().zip(word.chars()).all(|(point, ch)| {
+                        in_bounds(point)
+                            && match cells[index(point)] {
+                                None => true,
+                                Some(existing) => allow_overlap && existing == ch,
+                            }
+                    });
+                    if fits {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+
+        let placement = candidates
+            .choose(rng)
+            .ok_or_else(|| Error::CannotPlace(word.to_owned()))?
+            .clone();
+        for (point, ch) in placement.points().zip(word.chars()) {
+            cells[index(point)] = Some(ch);
+        }
+        key.push(placement);
+    }
+
+    let mut filler = filler.chars().collect::<Vec<_>>();
+    if filler.is_empty() {
+        filler = key.iter().flat_map(|m| m.word.chars()).collect();
+        filler.sort_unstable();
+        filler.dedup();
+    }
+    if filler.is_empty() {
+        filler.push('X');
+    }
+
+    // render as text and parse it, so the puzzle is exactly what the loader would produce
+    let mut text = String::with_capacity((width + 1) * height);
+    for y in (0..height).rev() {
+        for cell in &cells[y * width..(y + 1) * width] {
+            text.push(cell.unwrap_or_else(|| *filler.choose(rng).expect("filler is not empty")));
+        }
+        text.push('\n');
+    }
+    let grid = <WordSearch as TryFrom<&str>>::try_from(text.as_str())?;
+
+    key.sort();
+    Ok(GeneratedPuzzle { grid, key })
+}
+
+/// How to mark the cells which participate in a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Highlight {
+    /// Color matching letters with ANSI escapes
+    Color,
+    /// Replace non-matching letters with `.`
+    Dots,
+}
+
+/// Render `grid` with the `highlighted` cells marked.
+pub fn render(grid: &WordSearch, highlighted: &HashSet<Point>, style: Highlight) -> String {
+    grid.to_string_with_override(|point, tile| match style {
+        Highlight::Color => highlighted
+            .contains(&point)
+            .then(|| format!("\x1b[1;31m{tile}\x1b[0m")),
+        Highlight::Dots => (!highlighted.contains(&point)).then(|| ".".into()),
+    })
+}
+
+pub fn part1(input: &Path) -> Result<(), Error> {
+    let search_grid = <WordSearch as TryFrom<&Path>>::try_from(input)?;
+    let count = Bitboard::new(&search_grid).count_word(XMAS);
     println!("xmas count: {count}");
     Ok(())
 }
@@ -86,22 +596,310 @@ pub fn part1(input: &Path) -> Result<(), Error> {
 // not right: 15
 pub fn part2(input: &Path) -> Result<(), Error> {
     let search_grid = <WordSearch as TryFrom<&Path>>::try_from(input)?;
-
-    let count = search_grid
-        .iter()
-        .filter(|(origin, _tile)| is_x_mas(&search_grid, *origin))
-        .count();
+    let x_mas = Template::parse(X_MAS.trim(), DEFAULT_WILDCARD)?;
+    let count = find_patterns(&search_grid, &[x_mas]).len();
 
     println!("x-mas count: {count}");
     Ok(())
 }
 
+pub fn search(input: &Path, words: &[String]) -> Result<(), Error> {
+    let search_grid = <WordSearch as TryFrom<&Path>>::try_from(input)?;
+    let matches = find_words(&search_grid, words);
+
+    for m in &matches {
+        println!(
+            "{} at ({},{}) heading {}",
+            m.word, m.start.x, m.start.y, m.heading
+        );
+    }
+
+    let counts = MatchCounts::new(&matches);
+    println!("matches by word:");
+    for word in words {
+        let count = counts.by_word.get(word).copied().unwrap_or_default();
+        println!("  {word}: {count}");
+    }
+    println!("matches by heading:");
+    for (heading, count) in &counts.by_heading {
+        println!("  {heading}: {count}");
+    }
+    Ok(())
+}
+
+pub fn search_patterns(input: &Path, templates: &Path, wildcard: char) -> Result<(), Error> {
+    let search_grid = <WordSearch as TryFrom<&Path>>::try_from(input)?;
+    let templates = load_templates(templates, wildcard)?;
+    let matches = find_patterns(&search_grid, &templates);
+
+    let mut counts = vec![0; templates.len()];
+    for m in &matches {
+        println!(
+            "template {} at ({},{}) oriented {}",
+            m.template + 1,
+            m.anchor.x,
+            m.anchor.y,
+            m.orientation
+        );
+        counts[m.template] += 1;
+    }
+    println!("matches by template:");
+    for (idx, count) in counts.into_iter().enumerate() {
+        println!("  {}: {count}", idx + 1);
+    }
+    Ok(())
+}
+
+/// Render the grid with every cell which participates in a match highlighted.
+///
+/// When neither words nor templates are specified, searches for `XMAS`.
+pub fn render_matches(
+    input: &Path,
+    words: &[String],
+    templates: Option<&Path>,
+    wildcard: char,
+    style: Highlight,
+) -> Result<(), Error> {
+    let search_grid = <WordSearch as TryFrom<&Path>>::try_from(input)?;
+    let mut highlighted = HashSet::new();
+
+    if words.is_empty() && templates.is_none() {
+        for m in find_words(&search_grid, &[XMAS]) {
+            highlighted.extend(m.points());
+        }
+    }
+    for m in find_words(&search_grid, words) {
+        highlighted.extend(m.points());
+    }
+    if let Some(templates) = templates {
+        let templates = load_templates(templates, wildcard)?;
+        for m in find_patterns(&search_grid, &templates) {
+            highlighted.extend(m.points(&templates));
+        }
+    }
+
+    println!("{}", render(&search_grid, &highlighted, style));
+    Ok(())
+}
+
+/// Generate a word search hiding `words`.
+///
+/// The grid is written to `output`, or to stdout if unset; the answer key goes to stdout.
+pub fn generate_puzzle(
+    words: &[String],
+    options: &GeneratorOptions,
+    seed: Option<u64>,
+    output: Option<&Path>,
+) -> Result<(), Error> {
+    use rand::SeedableRng;
+    let mut rng = match seed {
+        Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+        None => rand::rngs::StdRng::from_entropy(),
+    };
+    let puzzle = generate(words, options, &mut rng)?;
+
+    let mut text = String::with_capacity((puzzle.grid.width() + 1) * puzzle.grid.height());
+    for y in (0..puzzle.grid.height() as i32).rev() {
+        for x in 0..puzzle.grid.width() as i32 {
+            text.push(*puzzle.grid[Point::new(x, y)]);
+        }
+        text.push('\n');
+    }
+    match output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{text}"),
+    }
+
+    println!("answer key:");
+    for m in &puzzle.key {
+        println!(
+            "  {} at ({},{}) heading {}",
+            m.word, m.start.x, m.start.y, m.heading
+        );
+    }
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("invalid input")]
     MapConversion(#[from] MapConversionErr),
+    #[error("pattern template has no letters:\n{0}")]
+    InvalidTemplate(String),
+    #[error("no room to place \"{0}\"")]
+    CannotPlace(String),
     #[error("no solution found")]
     NoSolution,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const EXAMPLE: &str = "
+MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+
+    fn example() -> WordSearch {
+        <WordSearch as TryFrom<&str>>::try_from(EXAMPLE.trim()).unwrap()
+    }
+
+    #[test]
+    fn xmas_count() {
+        let matches = find_words(&example(), &[XMAS]);
+        assert_eq!(matches.len(), 18);
+
+        let counts = MatchCounts::new(&matches);
+        assert_eq!(counts.by_word[XMAS], 18);
+        assert_eq!(counts.by_heading.values().sum::<usize>(), 18);
+        assert_eq!(counts.by_heading[&Heading::East], 3);
+        // the top row has an eastward XMAS at column 5
+        assert!(matches.contains(&WordMatch {
+            word: XMAS.into(),
+            start: Point::new(5, 9),
+            heading: Heading::East,
+        }));
+    }
+
+    #[test]
+    fn x_mas_count() {
+        let x_mas = Template::parse(X_MAS.trim(), DEFAULT_WILDCARD).unwrap();
+        assert_eq!(x_mas.orientations().len(), 4);
+        assert_eq!(find_patterns(&example(), &[x_mas]).len(), 9);
+    }
+
+    #[test]
+    fn orientations() {
+        let symmetric = Template::parse("A.A\n.A.\nA.A", DEFAULT_WILDCARD).unwrap();
+        assert_eq!(symmetric.orientations().len(), 1);
+
+        let line = Template::parse("XMAS", DEFAULT_WILDCARD).unwrap();
+        assert_eq!(line.orientations().len(), 4);
+        assert_eq!(find_patterns(&example(), &[line]).len(), 8);
+
+        let chiral = Template::parse("XM\n*A", '*').unwrap();
+        assert_eq!(chiral.orientations().len(), 8);
+    }
+
+    #[test]
+    fn render_dots() {
+        let grid = example();
+        let highlighted = find_words(&grid, &[XMAS])
+            .iter()
+            .flat_map(WordMatch::points)
+            .collect::<HashSet<_>>();
+        let rendered = render(&grid, &highlighted, Highlight::Dots);
+        let expect = "
+....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+";
+        assert_eq!(rendered.trim(), expect.trim());
+    }
+
+    /// A grid of pseudorandom letters from a small alphabet, so that words appear often.
+    fn random_grid(width: usize, height: usize, seed: u64) -> WordSearch {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let mut data = String::with_capacity((width + 1) * height);
+        for _ in 0..height {
+            for _ in 0..width {
+                data.push(*b"XMAS".choose(&mut rng).unwrap() as char);
+            }
+            data.push('\n');
+        }
+        <WordSearch as TryFrom<&str>>::try_from(data.as_str()).unwrap()
+    }
+
+    #[test]
+    fn bitboard_matches_scalar() {
+        let words = ["XMAS", "SAMX", "MAS", "XX", "A", "XMASXMASXMAS"];
+        for (width, height) in [(10, 10), (63, 5), (64, 9), (65, 7), (150, 70), (3, 200)] {
+            let grid = random_grid(width, height, (width * 1000 + height) as u64);
+            let bitboard = Bitboard::new(&grid);
+            let counts = MatchCounts::new(&find_words(&grid, &words));
+            for word in words {
+                assert_eq!(
+                    bitboard.count_word(word),
+                    counts.by_word.get(word).copied().unwrap_or_default(),
+                    "{word} in {width}x{height}"
+                );
+            }
+        }
+        assert_eq!(Bitboard::new(&example()).count_word(XMAS), 18);
+        assert_eq!(Bitboard::new(&example()).count_word("Q"), 0);
+    }
+
+    #[test]
+    fn generated_puzzle_is_solvable() {
+        use rand::SeedableRng;
+
+        let words = ["XMAS", "SANTA", "REINDEER", "ELF", "SLEIGH", "COAL", "A"];
+        for (seed, allow_overlap) in [(1, false), (2, true), (3, false), (4, true)] {
+            let options = GeneratorOptions {
+                width: 12,
+                height: 9,
+                allow_overlap,
+                filler: String::new(),
+            };
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let puzzle = generate(&words, &options, &mut rng).unwrap();
+            assert_eq!(puzzle.grid.width(), 12);
+            assert_eq!(puzzle.grid.height(), 9);
+            assert_eq!(puzzle.key.len(), words.len());
+
+            let found = find_words(&puzzle.grid, &words);
+            for placement in &puzzle.key {
+                assert!(found.contains(placement), "{placement:?} not found");
+            }
+
+            if !allow_overlap {
+                let mut occupied = HashSet::new();
+                for placement in &puzzle.key {
+                    for point in placement.points() {
+                        assert!(occupied.insert(point), "{placement:?} overlaps");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generate_impossible() {
+        let options = GeneratorOptions {
+            width: 3,
+            height: 3,
+            allow_overlap: false,
+            filler: "Z".into(),
+        };
+        let result = generate(&["XMAS"], &options, &mut rand::thread_rng());
+        assert!(matches!(result, Err(Error::CannotPlace(word)) if word == "XMAS"));
+    }
+
+    #[test]
+    fn several_words() {
+        let counts = MatchCounts::new(&find_words(&example(), &["XMAS", "SAMX", "MAS", "Q"]));
+        // every XMAS is also a SAMX in the opposite heading
+        assert_eq!(counts.by_word["XMAS"], counts.by_word["SAMX"]);
+        assert!(counts.by_word["MAS"] >= counts.by_word["XMAS"]);
+        assert!(!counts.by_word.contains_key("Q"));
+    }
+}
@@ -1,5 +1,8 @@
 use aoclib::{config::Config, website::get_input};
-use day04::{part1, part2};
+use day04::{
+    generate_puzzle, part1, part2, render_matches, search, search_patterns, GeneratorOptions,
+    Highlight, DEFAULT_WILDCARD,
+};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +25,56 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// search for each of these words, listing every match
+    #[arg(long, value_delimiter = ',')]
+    words: Vec<String>,
+
+    /// search for each pattern template in this file, in every rotation and reflection
+    ///
+    /// templates are separated by blank lines
+    #[arg(long)]
+    patterns: Option<PathBuf>,
+
+    /// character which matches any letter in a pattern template
+    #[arg(long, default_value_t = DEFAULT_WILDCARD)]
+    wildcard: char,
+
+    /// print the grid with every cell participating in a match highlighted
+    ///
+    /// highlights matches of `--words` and `--patterns`, or of `XMAS` if neither is set
+    #[arg(long, value_enum)]
+    render: Option<Highlight>,
+
+    /// instead of solving, generate a word search hiding `--words`
+    #[arg(long, requires = "words")]
+    generate: bool,
+
+    /// width of the generated word search
+    #[arg(long, default_value_t = 20)]
+    width: usize,
+
+    /// height of the generated word search
+    #[arg(long, default_value_t = 20)]
+    height: usize,
+
+    /// permit generated words to cross where they share a letter
+    #[arg(long)]
+    overlap: bool,
+
+    /// letters with which to fill the rest of the generated grid
+    ///
+    /// defaults to the letters of the words
+    #[arg(long, default_value = "")]
+    filler: String,
+
+    /// seed for the generator, for reproducible puzzles
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// write the generated grid to this file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
 }
 
 impl RunArgs {
@@ -42,6 +95,18 @@ impl RunArgs {
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::parse();
+
+    if args.generate {
+        let options = GeneratorOptions {
+            width: args.width,
+            height: args.height,
+            allow_overlap: args.overlap,
+            filler: args.filler.clone(),
+        };
+        generate_puzzle(&args.words, &options, args.seed, args.output.as_deref())?;
+        return Ok(());
+    }
+
     let input_path = args.input()?;
 
     if !args.no_part1 {
@@ -50,5 +115,20 @@ fn main() -> Result<()> {
     if args.part2 {
         part2(&input_path)?;
     }
+    if !args.words.is_empty() {
+        search(&input_path, &args.words)?;
+    }
+    if let Some(ref patterns) = args.patterns {
+        search_patterns(&input_path, patterns, args.wildcard)?;
+    }
+    if let Some(style) = args.render {
+        render_matches(
+            &input_path,
+            &args.words,
+            args.patterns.as_deref(),
+            args.wildcard,
+            style,
+        )?;
+    }
     Ok(())
 }
@@ -0,0 +1,77 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day05::{OrderingRule, Page, PrintJob, RuleIndex};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+const N_PAGES: usize = 500;
+const JOB_LEN: usize = 23;
+const N_JOBS: usize = 200;
+
+/// Generate `n_rules` consistent rules over a random total order of pages,
+/// and a set of jobs of which about half obey the rules.
+///
+/// The generator is seeded, so that every run benchmarks the same data.
+fn generate(n_rules: usize) -> (Vec<OrderingRule>, Vec<PrintJob>) {
+    let mut rng = StdRng::seed_from_u64(0x2024_1205);
+    let mut order = (10..10 + N_PAGES as Page).collect::<Vec<_>>();
+    order.shuffle(&mut rng);
+
+    let mut rules = Vec::with_capacity(n_rules);
+    while rules.len() < n_rules {
+        let a = rng.gen_range(0..N_PAGES);
+        let b = rng.gen_range(0..N_PAGES);
+        if a < b {
+            let rule = format!("{}|{}", order[a], order[b]);
+            rules.push(rule.parse().expect("generated rule is valid"));
+        }
+    }
+
+    let mut jobs = Vec::with_capacity(N_JOBS);
+    for job_idx in 0..N_JOBS {
+        let mut positions = (0..N_PAGES).collect::<Vec<_>>();
+        positions.shuffle(&mut rng);
+        positions.truncate(JOB_LEN);
+        if job_idx % 2 == 0 {
+            positions.sort_unstable();
+        }
+        let job = positions
+            .iter()
+            .map(|&position| order[position].to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        jobs.push(job.parse().expect("generated job is valid"));
+    }
+
+    (rules, jobs)
+}
+
+fn check_jobs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("check_jobs");
+    group.sample_size(10);
+    for n_rules in [1_000, 10_000, 100_000] {
+        let (rules, jobs) = generate(n_rules);
+
+        group.bench_with_input(BenchmarkId::new("checkers", n_rules), &rules, |b, rules| {
+            b.iter(|| jobs.iter().filter(|job| job.satisfies_rules(rules)).count())
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("index_with_build", n_rules),
+            &rules,
+            |b, rules| {
+                b.iter(|| {
+                    let index = RuleIndex::new(rules);
+                    jobs.iter().filter(|job| job.satisfies(&index)).count()
+                })
+            },
+        );
+
+        let index = RuleIndex::new(&rules);
+        group.bench_with_input(BenchmarkId::new("index", n_rules), &index, |b, index| {
+            b.iter(|| jobs.iter().filter(|job| job.satisfies(index)).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, check_jobs);
+criterion_main!(benches);
@@ -1,17 +1,24 @@
 use aoclib::CommaSep;
-use std::{path::Path, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    path::Path,
+    str::FromStr,
+};
 
-type Page = u32;
+pub type Page = u32;
 
-#[derive(Debug, Clone, Copy, Hash, parse_display::FromStr, parse_display::Display)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, parse_display::FromStr, parse_display::Display,
+)]
 #[display("{prior}|{later}")]
-struct OrderingRule {
+pub struct OrderingRule {
     prior: Page,
     later: Page,
 }
 
 impl OrderingRule {
-    fn checker(&self) -> PageOrderChecker {
+    fn checker(&self) -> PageOrderChecker<'_> {
         PageOrderChecker {
             order: self,
             first_match: None,
@@ -20,8 +27,177 @@ impl OrderingRule {
     }
 }
 
+/// Ordering rules, indexed by prior page.
+#[derive(Debug, Default, Clone)]
+pub struct RuleIndex {
+    /// Map from each page to the set of pages which must come after it
+    later: HashMap<Page, HashSet<Page>>,
+}
+
+impl RuleIndex {
+    pub fn new(rules: &[OrderingRule]) -> Self {
+        let mut later = HashMap::<Page, HashSet<Page>>::new();
+        for rule in rules {
+            later.entry(rule.prior).or_default().insert(rule.later);
+        }
+        Self { later }
+    }
+
+    /// Whether some rule requires `prior` to come before `later`.
+    pub fn requires(&self, prior: Page, later: Page) -> bool {
+        self.later
+            .get(&prior)
+            .is_some_and(|pages| pages.contains(&later))
+    }
+}
+
+/// A queryable model of which pages must precede which others.
+///
+/// This maintains the transitive closure of the rules added to it, so that queries are
+/// constant-time lookups. Rules which would introduce a cycle are rejected.
+///
+/// The full rule set of a puzzle input is generally cyclic; only the rules relevant to any single
+/// print job are guaranteed to be consistent. See [`PrintJob::precedence`].
+#[derive(Debug, Default, Clone)]
+pub struct Precedence {
+    /// Map from each page to the pages which its rules directly require to come after it
+    successors: HashMap<Page, Vec<Page>>,
+    /// Map from each page to every page which must come after it
+    descendants: HashMap<Page, HashSet<Page>>,
+    /// Map from each page to every page which must come before it
+    ancestors: HashMap<Page, HashSet<Page>>,
+}
+
+impl Precedence {
+    pub fn new(rules: &[OrderingRule]) -> Result<Self, Error> {
+        let mut precedence = Self::default();
+        for rule in rules {
+            precedence.add_rule(*rule)?;
+        }
+        Ok(precedence)
+    }
+
+    /// Add a rule, updating the closure.
+    ///
+    /// Every page which must precede the rule's prior page thereby must precede every page which
+    /// must follow its later page.
+    ///
+    /// Fails without modifying the model if the rule would create a cycle.
+    pub fn add_rule(&mut self, rule: OrderingRule) -> Result<(), Error> {
+        let OrderingRule { prior, later } = rule;
+        if prior == later || self.must_precede(later, prior) {
+            return Err(Error::CyclicRules(self.cycle_through(rule)));
+        }
+        self.successors.entry(prior).or_default().push(later);
+        self.descendants.entry(later).or_default();
+        self.ancestors.entry(prior).or_default();
+        if self.must_precede(prior, later) {
+            return Ok(());
+        }
+
+        let befores = self.ancestors[&prior]
+            .iter()
+            .copied()
+            .chain([prior])
+            .collect::<Vec<_>>();
+        let afters = self.descendants[&later]
+            .iter()
+            .copied()
+            .chain([later])
+            .collect::<Vec<_>>();
+        for &before in &befores {
+            self.descendants
+                .entry(before)
+                .or_default()
+                .extend(afters.iter().copied());
+        }
+        for &after in &afters {
+            self.ancestors
+                .entry(after)
+                .or_default()
+                .extend(befores.iter().copied());
+        }
+        Ok(())
+    }
+
+    /// The cycle which `rule` would close: a path of existing rules from its later page back
+    /// to its prior page.
+    fn cycle_through(&self, rule: OrderingRule) -> Vec<Page> {
+        let mut came_from = HashMap::new();
+        let mut queue = std::collections::VecDeque::from([rule.later]);
+        while let Some(page) = queue.pop_front() {
+            if page == rule.prior {
+                break;
+            }
+            for &successor in self.successors.get(&page).into_iter().flatten() {
+                if successor != rule.later && !came_from.contains_key(&successor) {
+                    came_from.insert(successor, page);
+                    queue.push_back(successor);
+                }
+            }
+        }
+
+        let mut cycle = vec![rule.prior];
+        let mut page = rule.prior;
+        while page != rule.later {
+            page = came_from[&page];
+            cycle.push(page);
+        }
+        cycle.reverse();
+        // `cycle` now runs from `later` to `prior`; the new rule closes it
+        cycle.rotate_right(1);
+        cycle
+    }
+
+    /// Whether the rules require `prior` to come before `later`, directly or transitively.
+    pub fn must_precede(&self, prior: Page, later: Page) -> bool {
+        self.descendants
+            .get(&prior)
+            .is_some_and(|pages| pages.contains(&later))
+    }
+
+    /// Whether the rules fix the relative order of `a` and `b`, in either direction.
+    pub fn is_ordered(&self, a: Page, b: Page) -> bool {
+        self.must_precede(a, b) || self.must_precede(b, a)
+    }
+
+    /// All pages mentioned by some rule, in ascending order.
+    pub fn pages(&self) -> Vec<Page> {
+        let mut pages = self
+            .descendants
+            .keys()
+            .chain(self.ancestors.keys())
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        pages.sort_unstable();
+        pages
+    }
+
+    /// Pages mentioned by some rule which the rules allow to come either before or after `page`,
+    /// in ascending order.
+    pub fn unordered_relative_to(&self, page: Page) -> Vec<Page> {
+        self.pages()
+            .into_iter()
+            .filter(|&other| other != page && !self.is_ordered(page, other))
+            .collect()
+    }
+
+    /// Whether exactly one ordering of this job's pages satisfies the rules.
+    ///
+    /// This is the case when every pair of pages in the job has a fixed relative order.
+    pub fn has_unique_ordering(&self, job: &PrintJob) -> bool {
+        job.pages.iter().enumerate().all(|(idx, &a)| {
+            job.pages[idx + 1..]
+                .iter()
+                .all(|&b| a == b || self.is_ordered(a, b))
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
-struct PrintJob {
+pub struct PrintJob {
     pages: Vec<Page>,
 }
 
@@ -38,8 +214,60 @@ impl FromStr for PrintJob {
     }
 }
 
+impl std::fmt::Display for PrintJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, page) in self.pages.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{page}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A rule which a print job breaks, and where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Violation {
+    rule: OrderingRule,
+    /// Index of the rule's prior page within the job
+    prior_position: usize,
+    /// Index of the rule's later page within the job
+    later_position: usize,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: page {} at position {} precedes page {} at position {}",
+            self.rule,
+            self.rule.later,
+            self.later_position + 1,
+            self.rule.prior,
+            self.prior_position + 1,
+        )
+    }
+}
+
 impl PrintJob {
-    fn satisfies_rules(&self, rules: &[OrderingRule]) -> bool {
+    /// Whether this job's pages are ordered according to the rules.
+    ///
+    /// Checks each pair of pages, so takes time quadratic in the length of the job,
+    /// independent of the number of rules.
+    pub fn satisfies(&self, index: &RuleIndex) -> bool {
+        self.pages.iter().enumerate().all(|(idx, &later)| {
+            self.pages[..idx]
+                .iter()
+                .all(|&prior| !index.requires(later, prior))
+        })
+    }
+
+    /// Whether this job's pages are ordered according to the rules, without an index.
+    ///
+    /// This feeds every page to a checker for every rule, so is much slower than [`Self::satisfies`].
+    /// The tests check the index against it, and the benchmarks measure how much the index saves.
+    pub fn satisfies_rules(&self, rules: &[OrderingRule]) -> bool {
         let mut checkers = Vec::with_capacity(rules.len());
         checkers.extend(rules.iter().map(OrderingRule::checker));
 
@@ -55,6 +283,114 @@ impl PrintJob {
         true
     }
 
+    /// Rules which apply to this job: those for which both pages appear in it.
+    fn relevant_rules(&self, index: &RuleIndex) -> Vec<OrderingRule> {
+        let mut rules = Vec::new();
+        for &prior in &self.pages {
+            for &later in &self.pages {
+                if index.requires(prior, later) {
+                    rules.push(OrderingRule { prior, later });
+                }
+            }
+        }
+        rules
+    }
+
+    /// Build a precedence model from the rules which apply to this job.
+    ///
+    /// Fails if those rules are cyclic.
+    pub fn precedence(&self, index: &RuleIndex) -> Result<Precedence, Error> {
+        Precedence::new(&self.relevant_rules(index))
+    }
+
+    /// List every relevant rule which this job breaks.
+    fn violations(&self, index: &RuleIndex) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (later_position, &later) in self.pages.iter().enumerate() {
+            for (prior_position, &prior) in self.pages.iter().enumerate().skip(later_position + 1) {
+                if index.requires(prior, later) {
+                    violations.push(Violation {
+                        rule: OrderingRule { prior, later },
+                        prior_position,
+                        later_position,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Produce a copy of this job whose pages are ordered according to the rules.
+    ///
+    /// This is a topological sort over the relevant rules. Where the rules leave several
+    /// pages free to come next, they keep their original relative order.
+    ///
+    /// Fails if the relevant rules are cyclic.
+    fn reordered(&self, index: &RuleIndex) -> Result<Self, Error> {
+        let position = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(idx, page)| (*page, idx))
+            .collect::<HashMap<_, _>>();
+
+        // indexed by position in the original job
+        let mut successors = vec![Vec::new(); self.pages.len()];
+        let mut n_predecessors = vec![0_usize; self.pages.len()];
+        for rule in self.relevant_rules(index) {
+            successors[position[&rule.prior]].push(position[&rule.later]);
+            n_predecessors[position[&rule.later]] += 1;
+        }
+
+        let mut placed = vec![false; self.pages.len()];
+        let mut pages = Vec::with_capacity(self.pages.len());
+        while pages.len() < self.pages.len() {
+            let Some(next) =
+                (0..self.pages.len()).find(|&idx| !placed[idx] && n_predecessors[idx] == 0)
+            else {
+                return Err(Error::CyclicRules(self.find_cycle(&successors, &placed)));
+            };
+            placed[next] = true;
+            pages.push(self.pages[next]);
+            for &successor in &successors[next] {
+                n_predecessors[successor] -= 1;
+            }
+        }
+
+        Ok(Self { pages })
+    }
+
+    /// Find a cycle among the unplaced pages.
+    ///
+    /// Every unplaced page has an unplaced predecessor, so walking backwards from any of them
+    /// must eventually revisit a page.
+    fn find_cycle(&self, successors: &[Vec<usize>], placed: &[bool]) -> Vec<Page> {
+        let predecessor_of = |idx: usize| {
+            (0..self.pages.len())
+                .find(|&pred| !placed[pred] && successors[pred].contains(&idx))
+                .expect("unplaced pages all have unplaced predecessors")
+        };
+
+        let start = placed
+            .iter()
+            .position(|placed| !placed)
+            .expect("a cycle implies unplaced pages");
+        let mut path = vec![start];
+        let mut current = predecessor_of(start);
+        while !path.contains(&current) {
+            path.push(current);
+            current = predecessor_of(current);
+        }
+
+        // `path` runs backwards along the rules; the cycle is the portion from `current` on
+        let cycle_start = path.iter().position(|&idx| idx == current).unwrap();
+        path[cycle_start..]
+            .iter()
+            .rev()
+            .map(|&idx| self.pages[idx])
+            .collect()
+    }
+
     fn middle_number(&self) -> Page {
         if self.pages.len() % 2 != 1 {
             panic!("even number of pages in print job");
@@ -114,6 +450,10 @@ impl PageOrderChecker<'_> {
 
 fn parse(input: &Path) -> Result<(Vec<OrderingRule>, Vec<PrintJob>), Error> {
     let data = std::fs::read_to_string(input)?;
+    parse_str(&data)
+}
+
+fn parse_str(data: &str) -> Result<(Vec<OrderingRule>, Vec<PrintJob>), Error> {
     let (before, after) = data.split_once("\n\n").ok_or(Error::InvalidInput)?;
     let ordering_rules = before
         .lines()
@@ -129,10 +469,11 @@ fn parse(input: &Path) -> Result<(Vec<OrderingRule>, Vec<PrintJob>), Error> {
 
 pub fn part1(input: &Path) -> Result<(), Error> {
     let (ordering_rules, print_jobs) = parse(input)?;
+    let index = RuleIndex::new(&ordering_rules);
 
     let middle_page_sum = print_jobs
         .iter()
-        .filter(|print_job| print_job.satisfies_rules(&ordering_rules))
+        .filter(|print_job| print_job.satisfies(&index))
         .map(PrintJob::middle_number)
         .sum::<Page>();
     println!("sum of middle pages: {middle_page_sum}");
@@ -140,56 +481,397 @@ pub fn part1(input: &Path) -> Result<(), Error> {
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let (ordering_rules, mut print_jobs) = parse(input)?;
+    let (ordering_rules, print_jobs) = parse(input)?;
+    let index = RuleIndex::new(&ordering_rules);
 
-    let mut swaps = 0;
+    let mut middle_page_sum = 0;
+    for job in print_jobs.iter().filter(|job| !job.satisfies(&index)) {
+        middle_page_sum += job.reordered(&index)?.middle_number();
+    }
 
-    // retain only incorrectly ordered jobs
-    print_jobs.retain(|job| !job.satisfies_rules(&ordering_rules));
-    for job in print_jobs.iter_mut() {
-        let relevant_rules =
-            {
-                let mut rr = Vec::with_capacity(ordering_rules.len());
-                rr.extend(ordering_rules.iter().filter(|rule| {
-                    job.pages.contains(&rule.prior) && job.pages.contains(&rule.later)
-                }));
-                rr
-            };
+    println!("sum of previously-incorrect middle pages, after reordering: {middle_page_sum}");
+    Ok(())
+}
 
-        while !job.satisfies_rules(&relevant_rules) {
-            for rule in &relevant_rules {
-                let p_idx = job
-                    .pages
-                    .iter()
-                    .position(|page| *page == rule.prior)
-                    .expect("relevant jobs contain the prior rule");
-                let l_idx = job
-                    .pages
-                    .iter()
-                    .position(|page| *page == rule.later)
-                    .expect("relevant jobs contain the later rule");
-                if p_idx > l_idx {
-                    job.pages.swap(p_idx, l_idx);
-                    swaps += 1;
-                }
+/// For each print job which breaks the rules, explain which rules it breaks and how to fix it.
+pub fn explain(input: &Path) -> Result<(), Error> {
+    let (ordering_rules, print_jobs) = parse(input)?;
+    let index = RuleIndex::new(&ordering_rules);
+
+    for (idx, job) in print_jobs.iter().enumerate() {
+        let violations = job.violations(&index);
+        if violations.is_empty() {
+            continue;
+        }
+
+        println!("job {}: {job}", idx + 1);
+        for violation in violations {
+            println!("  violates {violation}");
+        }
+        match job.reordered(&index) {
+            Ok(corrected) => println!("  corrected: {corrected}"),
+            Err(err) => println!("  cannot correct: {err}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// For each print job, report whether the rules permit exactly one ordering of its pages,
+/// and if not, which pages may be freely reordered relative to each other.
+pub fn uniqueness(input: &Path) -> Result<(), Error> {
+    let (ordering_rules, print_jobs) = parse(input)?;
+    let index = RuleIndex::new(&ordering_rules);
+
+    for (idx, job) in print_jobs.iter().enumerate() {
+        let precedence = match job.precedence(&index) {
+            Ok(precedence) => precedence,
+            Err(err) => {
+                println!("job {}: no valid ordering: {err}", idx + 1);
+                continue;
             }
+        };
+        if precedence.has_unique_ordering(job) {
+            println!("job {}: unique ordering", idx + 1);
+            continue;
+        }
+
+        println!("job {}: multiple orderings", idx + 1);
+        for &page in &job.pages {
+            let unordered = job
+                .pages
+                .iter()
+                .copied()
+                .filter(|&other| other != page && !precedence.is_ordered(page, other))
+                .map(|other| other.to_string())
+                .collect::<Vec<_>>();
+            if !unordered.is_empty() {
+                println!("  {page} is unordered relative to {}", unordered.join(","));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `rule` is implied by the others: its later page is reachable from its prior page
+/// by some path of two or more rules.
+fn is_transitively_redundant(rule: &OrderingRule, successors: &HashMap<Page, Vec<Page>>) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = successors
+        .get(&rule.prior)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&page| page != rule.later)
+        .collect::<Vec<_>>();
+    while let Some(page) = stack.pop() {
+        if page == rule.later {
+            return true;
+        }
+        if visited.insert(page) {
+            stack.extend(successors.get(&page).into_iter().flatten().copied());
         }
     }
+    false
+}
 
-    println!("total swaps: {swaps}");
+/// Render the rules as a Graphviz DOT graph.
+///
+/// When `job` is set, only the rules relevant to it are shown, pages are labeled with their
+/// positions, and the rules which the job violates are highlighted in red.
+/// Rules which are implied by other rules are dashed.
+fn to_dot(rules: &[OrderingRule], job: Option<&PrintJob>) -> String {
+    let index = RuleIndex::new(rules);
+    let rules = match job {
+        Some(job) => job.relevant_rules(&index),
+        None => rules.to_vec(),
+    };
+    let violated = job
+        .map(|job| {
+            job.violations(&index)
+                .into_iter()
+                .map(|violation| violation.rule)
+                .collect::<HashSet<_>>()
+        })
+        .unwrap_or_default();
 
-    let middle_page_sum = print_jobs.iter().map(PrintJob::middle_number).sum::<Page>();
+    let mut successors = HashMap::<Page, Vec<Page>>::new();
+    for rule in &rules {
+        successors.entry(rule.prior).or_default().push(rule.later);
+    }
 
-    println!("sum of previously-incorrect middle pages, after reordering: {middle_page_sum}");
+    // writing to a `String` cannot fail
+    let mut out = String::from("digraph rules {\n");
+    if let Some(job) = job {
+        for (idx, page) in job.pages.iter().enumerate() {
+            writeln!(out, "    {page} [label=\"{page}\\n#{}\"];", idx + 1).unwrap();
+        }
+    }
+    for rule in &rules {
+        let mut attributes = Vec::new();
+        if violated.contains(rule) {
+            attributes.push("color=red");
+            attributes.push("penwidth=2");
+        }
+        if is_transitively_redundant(rule, &successors) {
+            attributes.push("style=dashed");
+        }
+        write!(out, "    {} -> {}", rule.prior, rule.later).unwrap();
+        if !attributes.is_empty() {
+            write!(out, " [{}]", attributes.join(", ")).unwrap();
+        }
+        out.push_str(";\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Print the ordering rules as a Graphviz DOT graph.
+///
+/// `job` is the 1-based index of a print job to which to limit the graph.
+pub fn graphviz(input: &Path, job: Option<usize>) -> Result<(), Error> {
+    let (ordering_rules, print_jobs) = parse(input)?;
+    let job = job
+        .map(|idx| {
+            idx.checked_sub(1)
+                .and_then(|idx| print_jobs.get(idx))
+                .ok_or(Error::NoSuchJob(idx))
+        })
+        .transpose()?;
+    print!("{}", to_dot(&ordering_rules, job));
     Ok(())
 }
 
+/// Render a cycle of pages like `1 -> 2 -> 3 -> 1`.
+fn display_cycle(pages: &[Page]) -> String {
+    let mut out = String::new();
+    for page in pages.iter().chain(pages.first()) {
+        if !out.is_empty() {
+            out.push_str(" -> ");
+        }
+        out.push_str(&page.to_string());
+    }
+    out
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("invalid input")]
     InvalidInput,
+    #[error("no print job number {0}")]
+    NoSuchJob(usize),
+    #[error("ordering rules form a cycle: {}", display_cycle(.0))]
+    CyclicRules(Vec<Page>),
     #[error("no solution found")]
     NoSolution,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const EXAMPLE: &str = "
+47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47
+";
+
+    fn example() -> (Vec<OrderingRule>, Vec<PrintJob>) {
+        parse_str(EXAMPLE.trim()).unwrap()
+    }
+
+    #[test]
+    fn index_agrees_with_checkers() {
+        let (rules, jobs) = example();
+        let index = RuleIndex::new(&rules);
+        let satisfied = jobs
+            .iter()
+            .map(|job| job.satisfies(&index))
+            .collect::<Vec<_>>();
+        assert_eq!(satisfied, [true, true, true, false, false, false]);
+        for job in &jobs {
+            assert_eq!(job.satisfies(&index), job.satisfies_rules(&rules));
+        }
+    }
+
+    #[test]
+    fn reorder_example() {
+        let (rules, jobs) = example();
+        let index = RuleIndex::new(&rules);
+        let reordered = jobs
+            .iter()
+            .filter(|job| !job.satisfies(&index))
+            .map(|job| job.reordered(&index).unwrap())
+            .collect::<Vec<_>>();
+        let pages = reordered
+            .iter()
+            .map(|job| job.pages.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pages,
+            [
+                vec![97, 75, 47, 61, 53],
+                vec![61, 29, 13],
+                vec![97, 75, 47, 29, 13],
+            ]
+        );
+        assert!(reordered.iter().all(|job| job.satisfies(&index)));
+        assert_eq!(
+            reordered.iter().map(PrintJob::middle_number).sum::<Page>(),
+            123
+        );
+    }
+
+    #[test]
+    fn violations() {
+        let (rules, jobs) = example();
+        let index = RuleIndex::new(&rules);
+        let n_violations = jobs
+            .iter()
+            .map(|job| job.violations(&index).len())
+            .collect::<Vec<_>>();
+        assert_eq!(n_violations, [0, 0, 0, 1, 1, 4]);
+
+        assert_eq!(
+            jobs[3].violations(&index),
+            [Violation {
+                rule: OrderingRule {
+                    prior: 97,
+                    later: 75
+                },
+                prior_position: 1,
+                later_position: 0,
+            }]
+        );
+        assert_eq!(
+            jobs[3].violations(&index)[0].to_string(),
+            "97|75: page 75 at position 1 precedes page 97 at position 2"
+        );
+    }
+
+    #[test]
+    fn dot_for_job() {
+        let (rules, jobs) = example();
+        let dot = to_dot(&rules, Some(&jobs[3]));
+        assert!(dot.starts_with("digraph rules {\n"));
+        assert!(dot.contains("    75 [label=\"75\\n#1\"];\n"));
+        assert!(dot.contains("    97 -> 75 [color=red, penwidth=2];\n"));
+        // the job's pages are totally ordered by 97 -> 75 -> 47 -> 61 -> 53; every other rule is implied
+        assert_eq!(dot.matches(" -> ").count(), 10);
+        assert_eq!(dot.matches("style=dashed").count(), 6);
+        assert!(dot.contains("    47 -> 61;\n"));
+        assert!(dot.contains("    97 -> 53 [style=dashed];\n"));
+    }
+
+    #[test]
+    fn dot_for_all_rules() {
+        let (rules, _) = example();
+        let dot = to_dot(&rules, None);
+        assert_eq!(dot.matches(" -> ").count(), rules.len());
+        assert!(!dot.contains("color=red"));
+    }
+
+    #[test]
+    fn correct_jobs_are_unchanged() {
+        let (rules, jobs) = example();
+        let index = RuleIndex::new(&rules);
+        for job in jobs.iter().filter(|job| job.satisfies(&index)) {
+            assert_eq!(job.reordered(&index).unwrap().pages, job.pages);
+        }
+    }
+
+    #[test]
+    fn precedence_is_transitive() {
+        let (rules, _) = parse_str("1|2\n3|4\n2|3\n\n1").unwrap();
+        let precedence = Precedence::new(&rules).unwrap();
+        assert!(precedence.must_precede(1, 4));
+        assert!(precedence.must_precede(2, 4));
+        assert!(!precedence.must_precede(4, 1));
+        assert!(!precedence.must_precede(1, 1));
+        assert!(precedence.unordered_relative_to(1).is_empty());
+    }
+
+    #[test]
+    fn precedence_unordered_pages() {
+        let (rules, _) = parse_str("1|2\n1|3\n3|4\n\n1").unwrap();
+        let precedence = Precedence::new(&rules).unwrap();
+        assert_eq!(precedence.pages(), [1, 2, 3, 4]);
+        assert_eq!(precedence.unordered_relative_to(2), [3, 4]);
+        assert_eq!(precedence.unordered_relative_to(4), [2]);
+        assert!(precedence.unordered_relative_to(1).is_empty());
+    }
+
+    #[test]
+    fn precedence_rejects_cycles() {
+        let (rules, _) = parse_str("1|2\n2|3\n3|4\n\n1").unwrap();
+        let mut precedence = Precedence::new(&rules).unwrap();
+        let Err(Error::CyclicRules(cycle)) = precedence.add_rule("3|1".parse().unwrap()) else {
+            panic!("expected a cycle");
+        };
+        assert_eq!(cycle, [3, 1, 2]);
+        // the rejected rule leaves the model unchanged
+        assert!(!precedence.must_precede(3, 1));
+        assert!(precedence.add_rule("1|3".parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn unique_orderings() {
+        let (rules, jobs) = example();
+        let index = RuleIndex::new(&rules);
+        for job in &jobs {
+            // the example's rules order every pair of pages in every job
+            assert!(job.precedence(&index).unwrap().has_unique_ordering(job));
+        }
+
+        let (rules, jobs) = parse_str("1|2\n1|3\n\n1,2,3\n1,2").unwrap();
+        let precedence = Precedence::new(&rules).unwrap();
+        assert!(!precedence.has_unique_ordering(&jobs[0]));
+        assert!(precedence.has_unique_ordering(&jobs[1]));
+    }
+
+    #[test]
+    fn cycle_is_reported() {
+        let (rules, jobs) = parse_str("1|2\n2|3\n3|1\n1|4\n\n4,3,2,1").unwrap();
+        let job = &jobs[0];
+        let Err(Error::CyclicRules(cycle)) = job.reordered(&RuleIndex::new(&rules)) else {
+            panic!("expected a cycle");
+        };
+        // the cycle may begin at any of its pages
+        let start = cycle.iter().position(|&page| page == 1).unwrap();
+        let mut cycle = cycle;
+        cycle.rotate_left(start);
+        assert_eq!(cycle, [1, 2, 3]);
+        assert_eq!(
+            Error::CyclicRules(cycle).to_string(),
+            "ordering rules form a cycle: 1 -> 2 -> 3 -> 1"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day05::{part1, part2};
+use day05::{explain, graphviz, part1, part2, uniqueness};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,22 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// explain which rules each incorrectly-ordered print job violates, and how to correct it
+    #[arg(long)]
+    explain: bool,
+
+    /// print the ordering rules as a Graphviz DOT graph
+    #[arg(long)]
+    dot: bool,
+
+    /// limit the DOT graph to the pages of this print job (numbered from 1)
+    #[arg(long, requires = "dot")]
+    job: Option<usize>,
+
+    /// report whether each print job has exactly one valid ordering, and which pages may move
+    #[arg(long)]
+    unique: bool,
 }
 
 impl RunArgs {
@@ -50,5 +66,14 @@ fn main() -> Result<()> {
     if args.part2 {
         part2(&input_path)?;
     }
+    if args.explain {
+        explain(&input_path)?;
+    }
+    if args.dot {
+        graphviz(&input_path, args.job)?;
+    }
+    if args.unique {
+        uniqueness(&input_path)?;
+    }
     Ok(())
 }
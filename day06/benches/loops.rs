@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day06::{loop_obstacles, loop_obstacles_naive, parse_str, Guard, Map, TurnRight};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// A square lab of pseudorandom obstructions, with the guard in the middle.
+///
+/// The seed is one for which the guard leaves the lab at every benchmarked size, as counting
+/// loop obstacles requires.
+fn random_lab(size: usize) -> (Map, Guard) {
+    let mut rng = StdRng::seed_from_u64(0x2024_1208);
+    let mut data = String::with_capacity((size + 1) * size);
+    for y in 0..size {
+        for x in 0..size {
+            let tile = if x == size / 2 && y == size / 2 {
+                '^'
+            } else if rng.gen_bool(0.05) {
+                '#'
+            } else {
+                '.'
+            };
+            data.push(tile);
+        }
+        data.push('\n');
+    }
+    let (map, guards) = parse_str(&data).expect("generated lab is valid");
+    (map, guards[0])
+}
+
+fn loops(c: &mut Criterion) {
+    let mut group = c.benchmark_group("loop_obstacles");
+    group.sample_size(10);
+    for size in [65, 130, 260] {
+        let (map, guard) = random_lab(size);
+
+        group.bench_with_input(BenchmarkId::new("naive", size), &map, |b, map| {
+            b.iter(|| loop_obstacles_naive(map, guard, &TurnRight))
+        });
+
+        group.bench_with_input(BenchmarkId::new("jump_table", size), &map, |b, map| {
+            b.iter(|| loop_obstacles(map, guard, &TurnRight).unwrap().len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, loops);
+criterion_main!(benches);
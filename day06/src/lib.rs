@@ -1,42 +1,65 @@
 use aoclib::geometry::{tile::DisplayWidth, Direction, MapConversionErr, Point};
-use rayon::{iter::ParallelBridge, prelude::ParallelIterator};
-use std::path::Path;
+use rayon::iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Hash, parse_display::FromStr, parse_display::Display,
 )]
-enum Tile {
+pub enum Tile {
     #[display(".")]
     Blank,
     #[display("#")]
     Obstruction,
     #[display("^")]
-    Initial,
+    InitialUp,
+    #[display(">")]
+    InitialRight,
+    #[display("v")]
+    InitialDown,
+    #[display("<")]
+    InitialLeft,
+}
+
+impl Tile {
+    /// The orientation of the guard who starts on this tile, if any.
+    fn initial_orientation(self) -> Option<Direction> {
+        match self {
+            Tile::InitialUp => Some(Direction::Up),
+            Tile::InitialRight => Some(Direction::Right),
+            Tile::InitialDown => Some(Direction::Down),
+            Tile::InitialLeft => Some(Direction::Left),
+            Tile::Blank | Tile::Obstruction => None,
+        }
+    }
 }
 
 impl DisplayWidth for Tile {
     const DISPLAY_WIDTH: usize = 1;
 }
 
+fn direction_idx(direction: Direction) -> usize {
+    match direction {
+        Direction::Right => 0,
+        Direction::Left => 1,
+        Direction::Up => 2,
+        Direction::Down => 3,
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 struct VisitRecorder(u8);
 
 impl VisitRecorder {
-    fn direction_idx(index: Direction) -> usize {
-        match index {
-            Direction::Right => 0,
-            Direction::Left => 1,
-            Direction::Up => 2,
-            Direction::Down => 3,
-        }
-    }
-
     fn set(&mut self, direction: Direction) {
-        self.0 |= 1 << Self::direction_idx(direction);
+        self.0 |= 1 << direction_idx(direction);
     }
 
     fn is_set(&self, direction: Direction) -> bool {
-        self.0 & (1 << Self::direction_idx(direction)) != 0
+        self.0 & (1 << direction_idx(direction)) != 0
     }
 
     fn is_visited(&self) -> bool {
@@ -44,62 +67,374 @@ impl VisitRecorder {
     }
 }
 
-type Map = aoclib::geometry::map::Map<Tile>;
+pub type Map = aoclib::geometry::map::Map<Tile>;
 type Visited = aoclib::geometry::map::Map<VisitRecorder>;
+type Stops = aoclib::geometry::map::Map<[Option<Point>; 4]>;
+type Stamps = aoclib::geometry::map::Map<[u32; 4]>;
+type FirstSeen = aoclib::geometry::map::Map<[Option<usize>; 4]>;
 
-#[derive(Debug, Clone, parse_display::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
 #[display("G({position.x},{position.y};{orientation:?})")]
-struct Guard {
+pub struct Guard {
     position: Point,
     orientation: Direction,
 }
 
 impl Guard {
-    fn new(position: Point) -> Self {
+    fn new(position: Point, orientation: Direction) -> Self {
         Self {
             position,
-            orientation: Direction::Up,
+            orientation,
         }
     }
+
+    /// Describe this guard's position and orientation in puzzle coordinates.
+    fn describe(&self, map: &Map) -> String {
+        let Location { row, column } = Location::new(map, self.position);
+        format!(
+            "row {row}, column {column}, facing {}",
+            orientation_name(self.orientation)
+        )
+    }
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let map = <Map as TryFrom<&Path>>::try_from(input)?;
-    let mut guard = None;
-    for (position, tile) in map.iter() {
-        if *tile == Tile::Initial {
-            guard = Some(Guard::new(position));
-            break;
+/// How a guard chooses a new orientation when an obstruction blocks their way.
+///
+/// Policies must be deterministic: the same position and orientation always produce the same
+/// choice. Otherwise, revisiting a state would not prove that the guard is trapped in a loop.
+pub trait GuardPolicy: Sync {
+    /// Choose the guard's new orientation, given their position and current orientation.
+    fn turn(&self, position: Point, orientation: Direction) -> Direction;
+}
+
+/// Turn right at each obstruction, as the puzzle specifies.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TurnRight;
+
+impl GuardPolicy for TurnRight {
+    fn turn(&self, _position: Point, orientation: Direction) -> Direction {
+        orientation.turn_right()
+    }
+}
+
+/// Turn left at each obstruction.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TurnLeft;
+
+impl GuardPolicy for TurnLeft {
+    fn turn(&self, _position: Point, orientation: Direction) -> Direction {
+        orientation.turn_left()
+    }
+}
+
+/// Turn around at each obstruction.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TurnAround;
+
+impl GuardPolicy for TurnAround {
+    fn turn(&self, _position: Point, orientation: Direction) -> Direction {
+        orientation.reverse()
+    }
+}
+
+/// Turn right, left, or around at each obstruction, chosen pseudorandomly.
+///
+/// The choice is a hash of the seed and the guard's state, so it is the same each time the guard
+/// arrives at the same position facing the same way.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomTurn {
+    pub seed: u64,
+}
+
+impl GuardPolicy for RandomTurn {
+    fn turn(&self, position: Point, orientation: Direction) -> Direction {
+        // splitmix64 finalizer
+        let mut hash = self.seed
+            ^ (position.x as u32 as u64)
+            ^ ((position.y as u32 as u64) << 32)
+            ^ ((direction_idx(orientation) as u64) << 62);
+        hash = hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^= hash >> 31;
+        match hash % 3 {
+            0 => orientation.turn_right(),
+            1 => orientation.turn_left(),
+            _ => orientation.reverse(),
         }
     }
-    let mut guard = guard.ok_or(Error::GuardNotFound)?;
+}
+
+/// Guard policies selectable from the command line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PolicyKind {
+    #[default]
+    Right,
+    Left,
+    Around,
+    Random,
+}
+
+impl PolicyKind {
+    /// Construct this policy. `seed` is used only by [`PolicyKind::Random`].
+    pub fn policy(self, seed: u64) -> Box<dyn GuardPolicy> {
+        match self {
+            PolicyKind::Right => Box::new(TurnRight),
+            PolicyKind::Left => Box::new(TurnLeft),
+            PolicyKind::Around => Box::new(TurnAround),
+            PolicyKind::Random => Box::new(RandomTurn { seed }),
+        }
+    }
+}
+
+/// Find every guard on the map, in reading order.
+fn find_guards(map: &Map) -> Result<Vec<Guard>, Error> {
+    let mut guards = map
+        .iter()
+        .filter_map(|(position, tile)| {
+            tile.initial_orientation()
+                .map(|orientation| Guard::new(position, orientation))
+        })
+        .collect::<Vec<_>>();
+    if guards.is_empty() {
+        return Err(Error::GuardNotFound);
+    }
+    guards.sort_by_key(|guard| (Reverse(guard.position.y), guard.position.x));
+    Ok(guards)
+}
+
+pub fn parse_str(data: &str) -> Result<(Map, Vec<Guard>), Error> {
+    let map = <Map as TryFrom<&str>>::try_from(data)?;
+    let guards = find_guards(&map)?;
+    Ok((map, guards))
+}
+
+fn parse(input: &Path) -> Result<(Map, Vec<Guard>), Error> {
+    let map = <Map as TryFrom<&Path>>::try_from(input)?;
+    let guards = find_guards(&map)?;
+    Ok((map, guards))
+}
+
+/// The guard's state just before they first enter `position`.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    position: Point,
+    guard: Guard,
+}
+
+/// A guard's walk on the map as it stands.
+#[derive(Debug, Clone)]
+struct Trajectory {
+    /// The guard's state before each move, starting with their initial state
+    states: Vec<Guard>,
+    /// Whether the walk ended by returning to an earlier state rather than leaving the map
+    loops: bool,
+}
+
+/// Walk the guard's patrol one move at a time until they leave the map or repeat a state.
+///
+/// Turning in place counts as a move.
+fn trajectory(map: &Map, mut guard: Guard, policy: &dyn GuardPolicy) -> Trajectory {
     let mut visited = Visited::new(map.width(), map.height());
+    let mut states = Vec::new();
 
     while map.in_bounds(guard.position) {
+        if visited[guard.position].is_set(guard.orientation) {
+            return Trajectory {
+                states,
+                loops: true,
+            };
+        }
         visited[guard.position].set(guard.orientation);
+        states.push(guard);
         let forward = guard.position + guard.orientation;
-        // eprintln!(
-        //     "{guard} facing {}@({},{})",
-        //     map[forward], forward.x, forward.y
-        // );
 
         if map.in_bounds(forward) && map[forward] == Tile::Obstruction {
-            guard.orientation = guard.orientation.turn_right();
+            guard.orientation = policy.turn(guard.position, guard.orientation);
         } else {
             guard.position = forward;
         }
     }
 
-    let visited_count = visited.iter().filter(|(_, tile)| tile.is_visited()).count();
-    println!("visited locations: {visited_count}");
+    Trajectory {
+        states,
+        loops: false,
+    }
+}
 
-    Ok(())
+/// Walk the guard's patrol until they leave the map.
+///
+/// Returns a [`Step`] for each position the guard visits after their initial position,
+/// in order of first visit. Fails if the guard never leaves the map.
+fn patrol(map: &Map, guard: Guard, policy: &dyn GuardPolicy) -> Result<Vec<Step>, Error> {
+    let trajectory = trajectory(map, guard, policy);
+    if trajectory.loops {
+        return Err(Error::GuardLoops);
+    }
+
+    let mut visited = Visited::new(map.width(), map.height());
+    visited[guard.position].set(guard.orientation);
+    let mut steps = Vec::new();
+
+    for guard in trajectory.states {
+        let forward = guard.position + guard.orientation;
+        if map.in_bounds(forward)
+            && map[forward] != Tile::Obstruction
+            && !visited[forward].is_visited()
+        {
+            visited[forward].set(guard.orientation);
+            steps.push(Step {
+                position: forward,
+                guard,
+            });
+        }
+    }
+
+    Ok(steps)
+}
+
+/// For each position and direction, where a guard walking that way stops.
+///
+/// This lets the guard move from obstruction to obstruction instead of one step at a time.
+struct JumpTable {
+    /// The last position before the next obstruction, or `None` if the guard walks off the map
+    stops: Stops,
+}
+
+impl JumpTable {
+    fn new(map: &Map) -> Self {
+        let mut stops = Stops::new(map.width(), map.height());
+        for direction in Direction::iter() {
+            let (dx, dy) = direction.deltas();
+            // each position's stop depends on that of the position in front of it, so visit
+            // the positions furthest along `direction` first
+            let mut positions = map.iter().map(|(position, _)| position).collect::<Vec<_>>();
+            positions.sort_by_key(|position| Reverse(position.x * dx + position.y * dy));
+
+            let idx = direction_idx(direction);
+            for position in positions {
+                let forward = position + direction;
+                stops[position][idx] = if !map.in_bounds(forward) {
+                    None
+                } else if map[forward] == Tile::Obstruction {
+                    Some(position)
+                } else {
+                    stops[forward][idx]
+                };
+            }
+        }
+        Self { stops }
+    }
+
+    /// Where a guard walking from `position` toward `direction` stops, given an additional
+    /// obstacle.
+    fn stop(&self, position: Point, direction: Direction, obstacle: Point) -> Option<Point> {
+        let stop = self.stops[position][direction_idx(direction)];
+
+        let (dx, dy) = direction.deltas();
+        let along = |point: Point| (point.x - position.x) * dx + (point.y - position.y) * dy;
+        let across = (obstacle.x - position.x) * dy - (obstacle.y - position.y) * dx;
+        let distance = along(obstacle);
+        if across != 0 || distance <= 0 {
+            return stop;
+        }
+
+        // the obstacle is ahead; it matters if it comes before the stop
+        if stop.is_none_or(|stop| distance <= along(stop)) {
+            Some(position + (dx * (distance - 1), dy * (distance - 1)))
+        } else {
+            stop
+        }
+    }
+}
+
+/// Guard states seen during a walk.
+///
+/// Clearing increments a generation counter instead of touching every entry, so one buffer
+/// can be reused for many walks.
+struct VisitBuffer {
+    generation: u32,
+    stamps: Stamps,
+}
+
+impl VisitBuffer {
+    fn new(map: &Map) -> Self {
+        Self {
+            generation: 0,
+            stamps: Stamps::new(map.width(), map.height()),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamps = Stamps::new(self.stamps.width(), self.stamps.height());
+            self.generation = 1;
+        }
+    }
+
+    /// Record a guard state. Returns `false` if it was already recorded.
+    fn insert(&mut self, guard: Guard) -> bool {
+        let stamp = &mut self.stamps[guard.position][direction_idx(guard.orientation)];
+        let is_new = *stamp != self.generation;
+        *stamp = self.generation;
+        is_new
+    }
+}
+
+/// Whether the guard gets stuck in a loop when `obstacle` is added to the map.
+///
+/// Only the positions where the guard turns are recorded; revisiting any of them proves a loop.
+fn loops_with_obstacle(
+    jumps: &JumpTable,
+    policy: &dyn GuardPolicy,
+    mut guard: Guard,
+    obstacle: Point,
+    visited: &mut VisitBuffer,
+) -> bool {
+    visited.clear();
+    while let Some(stop) = jumps.stop(guard.position, guard.orientation, obstacle) {
+        guard.orientation = policy.turn(stop, guard.orientation);
+        guard.position = stop;
+        if !visited.insert(guard) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Find every position where a single new obstacle would trap the guard in a loop.
+///
+/// Only positions on the guard's original patrol can affect it. For each, the walk resumes
+/// from just before the guard first reaches it, as the patrol is unchanged up to that point.
+/// Obstacles cannot be placed where any guard starts.
+pub fn loop_obstacles(
+    map: &Map,
+    guard: Guard,
+    policy: &dyn GuardPolicy,
+) -> Result<Vec<Point>, Error> {
+    let steps = patrol(map, guard, policy)?;
+    let jumps = JumpTable::new(map);
+
+    Ok(steps
+        .par_iter()
+        .filter(|step| map[step.position] == Tile::Blank)
+        .map_init(
+            || VisitBuffer::new(map),
+            |visited, step| {
+                loops_with_obstacle(&jumps, policy, step.guard, step.position, visited)
+                    .then_some(step.position)
+            },
+        )
+        .flatten()
+        .collect())
 }
 
 fn produces_infinite_loop_with_additional_obstacle(
     mut guard: Guard,
     map: &Map,
     additional_obstacle: Point,
+    policy: &dyn GuardPolicy,
 ) -> bool {
     if map[additional_obstacle] != Tile::Blank {
         return false;
@@ -117,7 +452,7 @@ fn produces_infinite_loop_with_additional_obstacle(
         if forward == additional_obstacle
             || (map.in_bounds(forward) && map[forward] == Tile::Obstruction)
         {
-            guard.orientation = guard.orientation.turn_right();
+            guard.orientation = policy.turn(guard.position, guard.orientation);
         } else {
             guard.position = forward;
         }
@@ -126,31 +461,380 @@ fn produces_infinite_loop_with_additional_obstacle(
     false
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
-    let map = <Map as TryFrom<&Path>>::try_from(input)?;
-    let mut guard = None;
-    for (position, tile) in map.iter() {
-        if *tile == Tile::Initial {
-            guard = Some(Guard::new(position));
-            break;
-        }
-    }
-    let guard = guard.ok_or(Error::GuardNotFound)?;
-
-    let new_obstacles = map
-        .iter()
+/// Count the positions where a single new obstacle would trap the guard in a loop,
+/// by walking the full patrol step by step for every position on the map.
+///
+/// This is much slower than [`loop_obstacles`]. As it shares neither the original patrol nor
+/// the jump table, the tests use it as an independent check and the benchmarks as a baseline.
+pub fn loop_obstacles_naive(map: &Map, guard: Guard, policy: &dyn GuardPolicy) -> usize {
+    map.iter()
         .map(|(position, _)| position)
         .par_bridge()
         .filter(|additional_obstacle| {
             produces_infinite_loop_with_additional_obstacle(
-                guard.clone(),
-                &map,
+                guard,
+                map,
                 *additional_obstacle,
+                policy,
             )
         })
-        .count();
-    println!("potential new obstacles: {new_obstacles}");
+        .count()
+}
+
+/// How a guard becomes trapped in a loop by an additional obstacle.
+///
+/// Steps are counted as in [`trajectory`]: turning in place counts as a step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trap {
+    pub obstacle: Point,
+    /// Steps the guard takes before first entering the cycle
+    pub lead_in: usize,
+    /// Steps in one circuit of the cycle
+    pub cycle_length: usize,
+    /// Positions visited in the cycle, in order of first visit
+    pub cells: Vec<Point>,
+}
+
+/// Walk the guard's patrol with an additional obstacle, and characterize the loop which traps
+/// them, if any.
+pub fn characterize_loop(
+    map: &Map,
+    mut guard: Guard,
+    obstacle: Point,
+    policy: &dyn GuardPolicy,
+) -> Option<Trap> {
+    if map[obstacle] != Tile::Blank {
+        return None;
+    }
+
+    let mut first_seen = FirstSeen::new(map.width(), map.height());
+    let mut trajectory = Vec::new();
+
+    while map.in_bounds(guard.position) {
+        let seen = &mut first_seen[guard.position][direction_idx(guard.orientation)];
+        if let Some(cycle_start) = *seen {
+            let mut unique = HashSet::new();
+            let cells = trajectory[cycle_start..]
+                .iter()
+                .map(|state: &Guard| state.position)
+                .filter(|&position| unique.insert(position))
+                .collect();
+            return Some(Trap {
+                obstacle,
+                lead_in: cycle_start,
+                cycle_length: trajectory.len() - cycle_start,
+                cells,
+            });
+        }
+        *seen = Some(trajectory.len());
+        trajectory.push(guard);
+        let forward = guard.position + guard.orientation;
+
+        if forward == obstacle || (map.in_bounds(forward) && map[forward] == Tile::Obstruction) {
+            guard.orientation = policy.turn(guard.position, guard.orientation);
+        } else {
+            guard.position = forward;
+        }
+    }
+
+    None
+}
+
+/// Characterize the loop caused by each obstacle placement which traps the guard,
+/// ordered by how quickly they are trapped.
+pub fn characterize_loops(
+    map: &Map,
+    guard: Guard,
+    policy: &dyn GuardPolicy,
+) -> Result<Vec<Trap>, Error> {
+    let mut traps = loop_obstacles(map, guard, policy)?
+        .par_iter()
+        .map(|&obstacle| {
+            characterize_loop(map, guard, obstacle, policy).expect("loop obstacles produce loops")
+        })
+        .collect::<Vec<_>>();
+    traps.sort_by_key(|trap| (trap.lead_in, trap.cycle_length));
+    Ok(traps)
+}
+
+/// Every position the guard visits, in order of first visit.
+///
+/// A guard who never leaves the map is followed until they first repeat a state.
+fn visited_positions(map: &Map, guard: Guard, policy: &dyn GuardPolicy) -> Vec<Point> {
+    let mut visited = Visited::new(map.width(), map.height());
+    trajectory(map, guard, policy)
+        .states
+        .into_iter()
+        .filter_map(|state| {
+            let first_visit = !visited[state.position].is_visited();
+            visited[state.position].set(state.orientation);
+            first_visit.then_some(state.position)
+        })
+        .collect()
+}
+
+/// Apply `analysis` to each guard, printing each guard's count when there are several,
+/// and then the count of distinct positions among all guards.
+///
+/// Guards patrol independently of one another.
+fn report_per_guard(
+    map: &Map,
+    guards: &[Guard],
+    label: &str,
+    analysis: impl Fn(&Map, Guard) -> Result<Vec<Point>, Error>,
+) -> Result<(), Error> {
+    let mut combined = HashSet::new();
+    for (idx, guard) in guards.iter().enumerate() {
+        let positions = analysis(map, *guard)?;
+        if guards.len() > 1 {
+            println!(
+                "guard {} ({}): {label}: {}",
+                idx + 1,
+                guard.describe(map),
+                positions.len()
+            );
+        }
+        combined.extend(positions);
+    }
 
+    if guards.len() > 1 {
+        println!("all guards: {label}: {}", combined.len());
+    } else {
+        println!("{label}: {}", combined.len());
+    }
+    Ok(())
+}
+
+pub fn part1(input: &Path, policy: &dyn GuardPolicy) -> Result<(), Error> {
+    let (map, guards) = parse(input)?;
+    report_per_guard(&map, &guards, "visited locations", |map, guard| {
+        Ok(visited_positions(map, guard, policy))
+    })
+}
+
+pub fn part2(input: &Path, policy: &dyn GuardPolicy) -> Result<(), Error> {
+    let (map, guards) = parse(input)?;
+    report_per_guard(&map, &guards, "potential new obstacles", |map, guard| {
+        loop_obstacles(map, guard, policy)
+    })
+}
+
+/// A position in the lab, counted from the top left of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+struct Location {
+    row: i32,
+    column: i32,
+}
+
+impl Location {
+    fn new(map: &Map, position: Point) -> Self {
+        Self {
+            row: map.high_y() - position.y,
+            column: position.x - map.low_x(),
+        }
+    }
+}
+
+fn orientation_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Right => "right",
+        Direction::Left => "left",
+        Direction::Up => "up",
+        Direction::Down => "down",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+struct TrajectoryPoint {
+    /// Number of the guard, counting from 1 in reading order
+    guard: usize,
+    step: usize,
+    #[serde(flatten)]
+    location: Location,
+    orientation: &'static str,
+}
+
+/// An obstacle placement which traps at least one guard in a loop.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+struct LoopObstacle {
+    #[serde(flatten)]
+    location: Location,
+    /// Numbers of the guards which it traps
+    guards: Vec<usize>,
+}
+
+/// The guards' patrols and the obstacles which would trap them, in puzzle coordinates.
+#[derive(Debug, Clone, serde::Serialize)]
+struct Report {
+    trajectory: Vec<TrajectoryPoint>,
+    loop_obstacles: Vec<LoopObstacle>,
+}
+
+impl Report {
+    /// Build a report from each guard's trajectory and loop obstacles, in guard order.
+    fn new(map: &Map, patrols: &[Patrol]) -> Self {
+        let mut trajectory = Vec::new();
+        let mut loop_obstacles = Vec::<LoopObstacle>::new();
+        let mut obstacle_idx = HashMap::new();
+
+        for (guard_idx, patrol) in patrols.iter().enumerate() {
+            let guard = guard_idx + 1;
+            trajectory.extend(patrol.trajectory.iter().enumerate().map(|(step, state)| {
+                TrajectoryPoint {
+                    guard,
+                    step,
+                    location: Location::new(map, state.position),
+                    orientation: orientation_name(state.orientation),
+                }
+            }));
+            for &position in &patrol.loop_obstacles {
+                let idx = *obstacle_idx.entry(position).or_insert_with(|| {
+                    loop_obstacles.push(LoopObstacle {
+                        location: Location::new(map, position),
+                        guards: Vec::new(),
+                    });
+                    loop_obstacles.len() - 1
+                });
+                loop_obstacles[idx].guards.push(guard);
+            }
+        }
+
+        Self {
+            trajectory,
+            loop_obstacles,
+        }
+    }
+
+    /// Render as a single CSV table.
+    ///
+    /// The `kind` column distinguishes trajectory rows from obstacle rows. Obstacles get one row
+    /// per guard they trap, and leave the `step` and `orientation` columns empty.
+    fn to_csv(&self) -> String {
+        let mut out = String::from("kind,guard,step,row,column,orientation\n");
+        for point in &self.trajectory {
+            out.push_str(&format!(
+                "trajectory,{},{},{},{},{}\n",
+                point.guard,
+                point.step,
+                point.location.row,
+                point.location.column,
+                point.orientation
+            ));
+        }
+        for obstacle in &self.loop_obstacles {
+            for guard in &obstacle.guards {
+                out.push_str(&format!(
+                    "obstacle,{guard},,{},{},\n",
+                    obstacle.location.row, obstacle.location.column
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// A guard's full patrol, and the obstacle placements which would trap them.
+#[derive(Debug, Clone)]
+struct Patrol {
+    trajectory: Vec<Guard>,
+    loop_obstacles: Vec<Point>,
+}
+
+fn patrols(map: &Map, guards: &[Guard], policy: &dyn GuardPolicy) -> Result<Vec<Patrol>, Error> {
+    guards
+        .iter()
+        .map(|&guard| {
+            Ok(Patrol {
+                trajectory: trajectory(map, guard, policy).states,
+                loop_obstacles: loop_obstacles(map, guard, policy)?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// Print each guard's full trajectory and every obstacle placement which causes a loop.
+pub fn export(input: &Path, format: ExportFormat, policy: &dyn GuardPolicy) -> Result<(), Error> {
+    let (map, guards) = parse(input)?;
+    let report = Report::new(&map, &patrols(&map, &guards, policy)?);
+    match format {
+        ExportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        ExportFormat::Csv => print!("{}", report.to_csv()),
+    }
+    Ok(())
+}
+
+/// Draw the guards' paths as in the puzzle text.
+///
+/// Positions a guard crosses vertically are marked `|`, horizontally `-`, and both `+`.
+/// Obstacle placements which cause a loop for any guard are marked `O`.
+fn render_patrol(map: &Map, patrols: &[Patrol]) -> String {
+    let mut visited = Visited::new(map.width(), map.height());
+    for guard in patrols.iter().flat_map(|patrol| &patrol.trajectory) {
+        visited[guard.position].set(guard.orientation);
+    }
+    let loop_obstacles = patrols
+        .iter()
+        .flat_map(|patrol| &patrol.loop_obstacles)
+        .collect::<HashSet<_>>();
+
+    map.to_string_with_override(|position, tile| {
+        if loop_obstacles.contains(&position) {
+            return Some("O".into());
+        }
+        if *tile != Tile::Blank {
+            return None;
+        }
+        let visits = visited[position];
+        let vertical = visits.is_set(Direction::Up) || visits.is_set(Direction::Down);
+        let horizontal = visits.is_set(Direction::Left) || visits.is_set(Direction::Right);
+        match (vertical, horizontal) {
+            (true, true) => Some("+".into()),
+            (true, false) => Some("|".into()),
+            (false, true) => Some("-".into()),
+            (false, false) => None,
+        }
+    })
+}
+
+/// Print the map with the guards' paths and the obstacle placements which cause a loop.
+pub fn render(input: &Path, policy: &dyn GuardPolicy) -> Result<(), Error> {
+    let (map, guards) = parse(input)?;
+    print!("{}", render_patrol(&map, &patrols(&map, &guards, policy)?));
+    Ok(())
+}
+
+/// For each guard, print a characterization of the loop caused by each obstacle placement
+/// which traps them, fastest first.
+pub fn traps(input: &Path, policy: &dyn GuardPolicy) -> Result<(), Error> {
+    let (map, guards) = parse(input)?;
+    for (idx, guard) in guards.iter().enumerate() {
+        println!("guard {} ({}):", idx + 1, guard.describe(&map));
+        for trap in characterize_loops(&map, *guard, policy)? {
+            let Location { row, column } = Location::new(&map, trap.obstacle);
+            let cells = trap
+                .cells
+                .iter()
+                .map(|&cell| {
+                    let Location { row, column } = Location::new(&map, cell);
+                    format!("({row},{column})")
+                })
+                .collect::<Vec<_>>();
+            println!(
+                "  obstacle at row {row}, column {column}: {} steps before cycle, \
+                 cycle of {} steps through {} cells: {}",
+                trap.lead_in,
+                trap.cycle_length,
+                trap.cells.len(),
+                cells.join(" ")
+            );
+        }
+    }
     Ok(())
 }
 
@@ -160,8 +844,386 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("map converstion")]
     MapConversion(#[from] MapConversionErr),
+    #[error("serializing report")]
+    Json(#[from] serde_json::Error),
     #[error("initial guard position not found")]
     GuardNotFound,
+    #[error("guard patrols in a loop without an additional obstacle")]
+    GuardLoops,
     #[error("no solution found")]
     NoSolution,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+";
+
+    #[test]
+    fn part1_example() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        assert_eq!(patrol(&map, guard, &TurnRight).unwrap().len() + 1, 41);
+    }
+
+    #[test]
+    fn part2_example() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        assert_eq!(loop_obstacles(&map, guard, &TurnRight).unwrap().len(), 6);
+        assert_eq!(loop_obstacles_naive(&map, guard, &TurnRight), 6);
+    }
+
+    #[test]
+    fn trajectory_includes_turns() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        let trajectory = trajectory(&map, guard, &TurnRight).states;
+        assert_eq!(trajectory[0], guard);
+        // five steps up, then a turn in place
+        assert_eq!(trajectory[5].position, trajectory[6].position);
+        assert_eq!(trajectory[6].orientation, Direction::Right);
+    }
+
+    #[test]
+    fn report_formats() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let report = Report::new(&map, &patrols(&map, &guards, &TurnRight).unwrap());
+        assert_eq!(
+            report.trajectory[0],
+            TrajectoryPoint {
+                guard: 1,
+                step: 0,
+                location: Location { row: 6, column: 4 },
+                orientation: "up",
+            }
+        );
+        // the obstacle from the puzzle's first example loop
+        assert!(report.loop_obstacles.contains(&LoopObstacle {
+            location: Location { row: 6, column: 3 },
+            guards: vec![1],
+        }));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["trajectory"][0],
+            serde_json::json!({"guard": 1, "step": 0, "row": 6, "column": 4, "orientation": "up"})
+        );
+
+        let csv = report.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("kind,guard,step,row,column,orientation"));
+        assert_eq!(lines.next(), Some("trajectory,1,0,6,4,up"));
+        assert!(csv.contains("\nobstacle,1,,6,3,\n"));
+        assert_eq!(
+            csv.lines().count(),
+            1 + report.trajectory.len() + report.loop_obstacles.len()
+        );
+    }
+
+    #[test]
+    fn render_example() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let rendered = render_patrol(&map, &patrols(&map, &guards, &TurnRight).unwrap());
+        let expect = "
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+O^-+-+.
+.+----OO#.
+#O-O--+|..
+......#O..
+";
+        assert_eq!(rendered, expect.trim_start());
+    }
+
+    #[test]
+    fn initial_orientations() {
+        let (_, guards) = parse_str("^.>\n...\nv.<").unwrap();
+        let orientations = guards
+            .iter()
+            .map(|guard| guard.orientation)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            orientations,
+            [
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left
+            ]
+        );
+        assert!(matches!(parse_str("...\n.#."), Err(Error::GuardNotFound)));
+    }
+
+    #[test]
+    fn rotated_guard() {
+        // the example's guard, starting one step before their first turn, already turned right
+        let rotated = EXAMPLE
+            .replace('^', ".")
+            .replace("....#.....\n.........#", "....#.....\n....>....#");
+        let (map, guards) = parse_str(rotated.trim()).unwrap();
+        assert_eq!(guards[0].orientation, Direction::Right);
+        // the patrol is otherwise unchanged
+        let (original_map, original_guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let original = trajectory(&original_map, original_guards[0], &TurnRight).states;
+        assert_eq!(
+            trajectory(&map, guards[0], &TurnRight).states,
+            original[6..]
+        );
+    }
+
+    #[test]
+    fn multiple_guards() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let original_obstacles = loop_obstacles(&map, guards[0], &TurnRight).unwrap();
+
+        // a second guard, standing on the first guard's path
+        let two_guards = EXAMPLE.replacen("#.........", "#>........", 1);
+        let (map, guards) = parse_str(two_guards.trim()).unwrap();
+        assert_eq!(guards.len(), 2);
+        assert_eq!(guards[1].orientation, Direction::Right);
+
+        // guards patrol independently; the second guard's starting position is off-limits
+        let second_start = guards[1].position;
+        let expect = original_obstacles
+            .iter()
+            .copied()
+            .filter(|&position| position != second_start)
+            .collect::<Vec<_>>();
+        assert_eq!(loop_obstacles(&map, guards[0], &TurnRight).unwrap(), expect);
+
+        // the second guard walks straight off the map
+        assert_eq!(visited_positions(&map, guards[1], &TurnRight).len(), 9);
+        assert!(loop_obstacles(&map, guards[1], &TurnRight)
+            .unwrap()
+            .is_empty());
+
+        let report = Report::new(&map, &patrols(&map, &guards, &TurnRight).unwrap());
+        assert!(report.trajectory.iter().any(|point| point.guard == 2));
+        assert_eq!(report.loop_obstacles.len(), expect.len());
+        assert!(report
+            .loop_obstacles
+            .iter()
+            .all(|obstacle| obstacle.guards == [1]));
+    }
+
+    #[test]
+    fn characterize_example_loops() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        let traps = characterize_loops(&map, guard, &TurnRight).unwrap();
+        assert_eq!(traps.len(), 6);
+        assert!(traps
+            .windows(2)
+            .all(|pair| pair[0].lead_in <= pair[1].lead_in));
+
+        let path = visited_positions(&map, guard, &TurnRight);
+        for trap in &traps {
+            // a cycle needs at least four turns
+            assert!(trap.cycle_length >= 8);
+            assert!(trap.cells.len() <= trap.cycle_length);
+            assert!(!trap.cells.contains(&trap.obstacle));
+            // the obstacle must be on the original path, but the cycle can diverge from it
+            assert!(path.contains(&trap.obstacle));
+        }
+
+        // the puzzle's first example: the guard turns at the new obstacle beside their start
+        let first = traps
+            .iter()
+            .find(|trap| Location::new(&map, trap.obstacle) == Location { row: 6, column: 3 })
+            .unwrap();
+        assert!(first.cells.contains(&guard.position));
+        assert_eq!(
+            characterize_loop(&map, guard, guard.position, &TurnRight),
+            None
+        );
+    }
+
+    #[test]
+    fn characterize_small_loop() {
+        // the guard walks up, then around a rectangle which the obstacle closes
+        let (map, guards) = parse_str(
+            "
+.#....
+.....#
+......
+....#.
+......
+.^....
+",
+        )
+        .unwrap();
+        let guard = guards[0];
+        assert!(loop_obstacles(&map, guard, &TurnRight).is_ok());
+
+        let obstacle = Point::new(0, 3);
+        let trap = characterize_loop(&map, guard, obstacle, &TurnRight).unwrap();
+        // the cycle begins where the guard first passes the obstacle
+        assert_eq!(trap.lead_in, 3);
+        // eight moves and four turns
+        assert_eq!(trap.cycle_length, 12);
+        let cells = trap
+            .cells
+            .iter()
+            .map(|&cell| {
+                let Location { row, column } = Location::new(&map, cell);
+                (row, column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [
+                (2, 1),
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (1, 4),
+                (2, 4),
+                (2, 3),
+                (2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn jumps_stop_before_obstacles() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        let jumps = JumpTable::new(&map);
+        // the guard starts at (4, 3) and walks up to just below the obstruction at (4, 9)
+        assert_eq!(guard.position, Point::new(4, 3));
+        let nowhere = Point::new(0, 0);
+        assert_eq!(
+            jumps.stop(guard.position, Direction::Up, nowhere),
+            Some(Point::new(4, 8))
+        );
+        assert_eq!(
+            jumps.stop(guard.position, Direction::Up, Point::new(4, 6)),
+            Some(Point::new(4, 5))
+        );
+        assert_eq!(
+            jumps.stop(guard.position, Direction::Up, Point::new(4, 2)),
+            Some(Point::new(4, 8))
+        );
+        assert_eq!(jumps.stop(guard.position, Direction::Down, nowhere), None);
+        assert_eq!(
+            jumps.stop(guard.position, Direction::Down, Point::new(4, 0)),
+            Some(Point::new(4, 1))
+        );
+    }
+
+    #[test]
+    fn agrees_with_naive() {
+        let policies: [&dyn GuardPolicy; 5] = [
+            &TurnRight,
+            &TurnLeft,
+            &TurnAround,
+            &RandomTurn { seed: 1 },
+            &RandomTurn { seed: 2 },
+        ];
+        // variations on the example, each with one obstruction removed
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        for (position, _) in map.iter().filter(|(_, tile)| **tile == Tile::Obstruction) {
+            let mut map = map.clone();
+            map[position] = Tile::Blank;
+            for policy in policies {
+                if trajectory(&map, guard, policy).loops {
+                    assert!(matches!(
+                        loop_obstacles(&map, guard, policy),
+                        Err(Error::GuardLoops)
+                    ));
+                    continue;
+                }
+                let obstacles = loop_obstacles(&map, guard, policy).unwrap();
+                assert_eq!(obstacles.len(), loop_obstacles_naive(&map, guard, policy));
+                for obstacle in obstacles {
+                    assert!(characterize_loop(&map, guard, obstacle, policy).is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn visits_until_trapped() {
+        // turning left, the guard circles the rectangle forever; turning right, they escape
+        let (map, guards) = parse_str(
+            "
+....#.
+#.....
+......
+....^.
+.....#
+.#....
+"
+            .trim(),
+        )
+        .unwrap();
+        let guard = guards[0];
+        assert!(trajectory(&map, guard, &TurnLeft).loops);
+        assert_eq!(visited_positions(&map, guard, &TurnLeft).len(), 12);
+        assert!(matches!(
+            loop_obstacles(&map, guard, &TurnLeft),
+            Err(Error::GuardLoops)
+        ));
+        assert!(!trajectory(&map, guard, &TurnRight).loops);
+    }
+
+    #[test]
+    fn turn_left_mirrors_turn_right() {
+        let mirrored = EXAMPLE
+            .trim()
+            .lines()
+            .map(|line| line.chars().rev().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (map, guards) = parse_str(&mirrored).unwrap();
+        let guard = guards[0];
+        assert_eq!(visited_positions(&map, guard, &TurnLeft).len(), 41);
+        assert_eq!(loop_obstacles(&map, guard, &TurnLeft).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn turn_around_retraces_path() {
+        let (map, guards) = parse_str(EXAMPLE.trim()).unwrap();
+        let guard = guards[0];
+        let trajectory = trajectory(&map, guard, &TurnAround).states;
+        // six positions up to the first obstruction, a turn, then eight positions back down
+        // and off the bottom of the map
+        assert_eq!(trajectory.len(), 6 + 1 + 8);
+        assert!(trajectory
+            .iter()
+            .all(|state| state.position.x == guard.position.x));
+    }
+
+    #[test]
+    fn random_turn_is_deterministic() {
+        let policy = RandomTurn { seed: 6 };
+        let position = Point::new(3, 4);
+        for orientation in Direction::iter() {
+            let turned = policy.turn(position, orientation);
+            assert_ne!(turned, orientation);
+            assert_eq!(policy.turn(position, orientation), turned);
+        }
+        let choices = (0..20)
+            .map(|x| policy.turn(Point::new(x, 0), Direction::Up))
+            .collect::<HashSet<_>>();
+        assert_eq!(choices.len(), 3);
+    }
+}
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day06::{part1, part2};
+use day06::{export, part1, part2, render, traps, ExportFormat, PolicyKind};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,26 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// print the guard's trajectory and the obstacle placements which cause a loop
+    #[arg(long)]
+    export: Option<ExportFormat>,
+
+    /// draw the guard's path and the obstacle placements which cause a loop
+    #[arg(long)]
+    render: bool,
+
+    /// characterize the loop caused by each obstacle placement which traps a guard
+    #[arg(long)]
+    traps: bool,
+
+    /// how the guard turns at an obstruction
+    #[arg(long, value_enum, default_value_t)]
+    policy: PolicyKind,
+
+    /// seed for the random guard policy
+    #[arg(long, default_value_t)]
+    seed: u64,
 }
 
 impl RunArgs {
@@ -43,12 +63,23 @@ fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::parse();
     let input_path = args.input()?;
+    let policy = args.policy.policy(args.seed);
+    let policy = policy.as_ref();
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, policy)?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, policy)?;
+    }
+    if let Some(format) = args.export {
+        export(&input_path, format, policy)?;
+    }
+    if args.render {
+        render(&input_path, policy)?;
+    }
+    if args.traps {
+        traps(&input_path, policy)?;
     }
     Ok(())
 }
@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day07::{Equation, Operation, Order};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Pseudorandom equations with `n_terms` single-digit values each.
+///
+/// Half are solvable; the rest have their test values offset by one, which usually makes them
+/// unsolvable, forcing an exhaustive search.
+fn random_equations(n_terms: usize) -> Vec<Equation> {
+    let mut rng = StdRng::seed_from_u64(0x2024_1207);
+
+    (0..20)
+        .map(|idx| {
+            let values = (0..n_terms)
+                .map(|_| rng.gen_range(1..=9))
+                .collect::<Vec<_>>();
+            let mut test_value = values[0];
+            for &value in &values[1..] {
+                test_value = match rng.gen_range(0..3) {
+                    0 => test_value + value,
+                    1 => test_value * value,
+                    _ => test_value * 10 + value,
+                };
+            }
+            if idx % 2 == 1 {
+                test_value += 1;
+            }
+            let values = values
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("{test_value}: {values}")
+                .parse()
+                .expect("generated equation is valid")
+        })
+        .collect()
+}
+
+fn solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve_with_concat");
+    group.sample_size(10);
+    for n_terms in [6, 9, 12] {
+        let equations = random_equations(n_terms);
+
+        group.bench_with_input(
+            BenchmarkId::new("brute_force", n_terms),
+            &equations,
+            |b, equations| {
+                b.iter(|| {
+                    equations
+                        .iter()
+                        .filter(|equation| {
+                            equation.brute_force(Operation::PART2, Order::LeftToRight)
+                        })
+                        .count()
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("right_to_left", n_terms),
+            &equations,
+            |b, equations| {
+                b.iter(|| {
+                    equations
+                        .iter()
+                        .filter(|equation| equation.solvable(Operation::PART2, Order::LeftToRight))
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, solve);
+criterion_main!(benches);
@@ -1,10 +1,91 @@
 use aoclib::parse;
-use std::{path::Path, str::FromStr};
+use num_bigint::BigInt;
+use num_integer::Roots;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Signed, ToPrimitive};
+use std::{fmt, ops::ControlFlow, path::Path, str::FromStr};
+
+/// Integer types in which equations can be evaluated.
+///
+/// [`i64`] is fast, but treats equations whose evaluation overflows as unsolvable.
+/// [`BigInt`] represents every value exactly.
+pub trait Number:
+    Clone
+    + Ord
+    + fmt::Debug
+    + fmt::Display
+    + FromStr<Err: Into<Error>>
+    + Signed
+    + ToPrimitive
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + CheckedDiv
+{
+    /// Bitwise exclusive or.
+    fn xor(&self, rhs: &Self) -> Self;
+
+    /// Raise to a power, or `None` if that overflows or the exponent is negative.
+    fn checked_power(&self, exponent: &Self) -> Option<Self>;
+
+    /// The greatest integer whose `exponent`th power does not exceed this value's magnitude.
+    fn magnitude_root(&self, exponent: u32) -> Self;
+
+    /// Ten to the number of decimal digits in this value's magnitude; zero has one digit.
+    ///
+    /// Concatenation shifts its left operand by this much.
+    fn concat_shift(&self) -> Option<Self>;
+}
+
+impl Number for i64 {
+    fn xor(&self, rhs: &Self) -> Self {
+        self ^ rhs
+    }
+
+    fn checked_power(&self, exponent: &Self) -> Option<Self> {
+        i64::checked_pow(*self, u32::try_from(*exponent).ok()?)
+    }
+
+    fn magnitude_root(&self, exponent: u32) -> Self {
+        i64::try_from(self.unsigned_abs().nth_root(exponent)).unwrap_or(i64::MAX)
+    }
+
+    fn concat_shift(&self) -> Option<Self> {
+        10_i64.checked_pow(self.unsigned_abs().checked_ilog10().unwrap_or(0) + 1)
+    }
+}
+
+/// Powers are refused when their result would exceed about this many bits,
+/// so that a large exponent cannot exhaust memory.
+const MAX_POWER_BITS: u64 = 1 << 16;
+
+impl Number for BigInt {
+    fn xor(&self, rhs: &Self) -> Self {
+        self ^ rhs
+    }
+
+    fn checked_power(&self, exponent: &Self) -> Option<Self> {
+        let exponent = exponent.to_u32()?;
+        let bits = self
+            .bits()
+            .saturating_sub(1)
+            .saturating_mul(exponent.into());
+        (bits <= MAX_POWER_BITS).then(|| self.pow(exponent))
+    }
+
+    fn magnitude_root(&self, exponent: u32) -> Self {
+        self.magnitude().nth_root(exponent).into()
+    }
+
+    fn concat_shift(&self) -> Option<Self> {
+        let n_digits = u32::try_from(self.magnitude().to_string().len()).ok()?;
+        Some(BigInt::from(10).pow(n_digits))
+    }
+}
 
 #[derive(Debug)]
-struct Values(Vec<i64>);
+struct Values<N>(Vec<N>);
 
-impl FromStr for Values {
+impl<N: Number> FromStr for Values<N> {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -18,153 +99,1006 @@ impl FromStr for Values {
 
 #[derive(Debug, parse_display::FromStr)]
 #[display("{test_value}: {values}")]
-struct Equation {
-    test_value: i64,
-    values: Values,
+pub struct Equation<N: Number = i64> {
+    test_value: N,
+    values: Values<N>,
 }
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
-enum Operation {
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Deserialize,
+    parse_display::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
     #[default]
+    #[display("+")]
     Add,
+    #[display("*")]
     Mul,
+    /// Concatenation: `lhs || rhs` is `lhs` shifted left by the decimal digits of `rhs`'s
+    /// magnitude, plus `rhs`
+    ///
+    /// For non-negative operands this appends the digits of `rhs` to those of `lhs`. Zero has
+    /// one digit, so `12 || 0` is `120`; with a negative right operand, `12 || -3` is `117`.
+    #[display("||")]
     Concat,
+    #[display("-")]
+    Sub,
+    #[display("^")]
+    Xor,
+    /// Exponentiation
+    #[display("**")]
+    Pow,
+    /// Integer division, truncating toward zero
+    #[display("/")]
+    Div,
 }
 
-impl Operation {
-    /// Continue to the next operation in sequence.
+/// How tightly concatenation binds under [`Order::Standard`], relative to the arithmetic
+/// operations.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Deserialize,
+    parse_display::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[display(style = "lowercase")]
+pub enum ConcatPrecedence {
+    /// Tighter than every other operation: `2 * 3 || 4` is `2 * 34`
+    #[default]
+    Tightest,
+    /// Alongside multiplication and division: `2 * 3 || 4` is `6 || 4`
+    Multiplicative,
+    /// Alongside addition and subtraction: `1 + 2 || 3 * 4` is `3 || 12`
+    Additive,
+    /// Looser than every other operation: `1 || 2 ^ 3` is `1 || 1`
+    Loosest,
+}
+
+/// How the operations of an equation group.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Strictly left to right, ignoring precedence
+    #[default]
+    LeftToRight,
+    /// By precedence: among the arithmetic operations, `**` binds tightest, then `*` and `/`,
+    /// then `+` and `-`, then `^`. Where `||` fits is set by the [`ConcatPrecedence`].
     ///
-    /// Return `true` if this has overflowed back to the initial state.
-    fn increment(&mut self) -> bool {
-        *self = match self {
-            Operation::Add => Operation::Mul,
-            Operation::Mul => Operation::Concat,
-            Operation::Concat => Operation::Add,
+    /// Operations of equal precedence group left to right; this includes `**`.
+    Standard(ConcatPrecedence),
+}
+
+impl Order {
+    /// A parenthesized description for output; empty when evaluating left to right.
+    fn annotation(self) -> String {
+        match self {
+            Order::LeftToRight => String::new(),
+            Order::Standard(concat) => format!(" (standard precedence, concat {concat})"),
+        }
+    }
+}
+
+/// Left operands which an operation might combine with a known right operand to produce a
+/// known result.
+///
+/// Candidates are not necessarily valid; each must be checked by applying the operation.
+enum Preimage<N> {
+    /// Every left operand produces the result
+    Any,
+    Candidates([Option<N>; 2]),
+}
+
+impl<N: Number> Preimage<N> {
+    fn none() -> Self {
+        Self::Candidates([None, None])
+    }
+
+    fn one(lhs: Option<N>) -> Self {
+        Self::Candidates([lhs, None])
+    }
+
+    /// Every distinct candidate left operand; none for [`Preimage::Any`].
+    fn candidates(self) -> impl Iterator<Item = N> {
+        let pair = match self {
+            Preimage::Any => [None, None],
+            Preimage::Candidates([first, second]) if first == second => [first, None],
+            Preimage::Candidates(pair) => pair,
         };
-        *self == Self::Add
+        pair.into_iter().flatten()
+    }
+}
+
+/// `numerator / denominator`, if that is exact.
+fn exact_quotient<N: Number>(numerator: &N, denominator: &N) -> Option<N> {
+    numerator
+        .checked_div(denominator)
+        .filter(|quotient| quotient.checked_mul(denominator).as_ref() == Some(numerator))
+}
+
+impl Operation {
+    /// Operations available in part 1.
+    pub const PART1: &[Operation] = &[Operation::Add, Operation::Mul];
+    /// Operations available in part 2.
+    pub const PART2: &[Operation] = &[Operation::Add, Operation::Mul, Operation::Concat];
+
+    /// Compute `lhs <op> rhs`, or `None` if that overflows or is undefined.
+    fn apply<N: Number>(self, lhs: &N, rhs: &N) -> Option<N> {
+        match self {
+            Operation::Add => lhs.checked_add(rhs),
+            Operation::Mul => lhs.checked_mul(rhs),
+            Operation::Concat => lhs.checked_mul(&rhs.concat_shift()?)?.checked_add(rhs),
+            Operation::Sub => lhs.checked_sub(rhs),
+            Operation::Xor => Some(lhs.xor(rhs)),
+            Operation::Pow => lhs.checked_power(rhs),
+            Operation::Div => lhs.checked_div(rhs),
+        }
+    }
+
+    /// Whether [`Self::undo`] supports this operation.
+    ///
+    /// Truncating division discards a remainder, so as many left operands as the magnitude of
+    /// the divisor produce each result: far too many to enumerate.
+    fn invertible(self) -> bool {
+        self != Operation::Div
+    }
+
+    /// Find the left operands `lhs` which might satisfy `lhs <op> rhs == result`.
+    ///
+    /// Panics if this operation is not [invertible](Self::invertible).
+    fn undo<N: Number>(self, result: &N, rhs: &N) -> Preimage<N> {
+        match self {
+            Operation::Add => Preimage::one(result.checked_sub(rhs)),
+            Operation::Mul if rhs.is_zero() => match result.is_zero() {
+                true => Preimage::Any,
+                false => Preimage::none(),
+            },
+            Operation::Mul => Preimage::one(exact_quotient(result, rhs)),
+            Operation::Concat => {
+                let Some(shift) = rhs.concat_shift() else {
+                    return Preimage::none();
+                };
+                // for non-negative operands, this requires the result to end with `rhs`'s digits
+                Preimage::one(
+                    result
+                        .checked_sub(rhs)
+                        .and_then(|shifted| exact_quotient(&shifted, &shift)),
+                )
+            }
+            Operation::Sub => Preimage::one(result.checked_add(rhs)),
+            Operation::Xor => Preimage::one(Some(result.xor(rhs))),
+            Operation::Pow if rhs.is_zero() => match result.is_one() {
+                true => Preimage::Any,
+                false => Preimage::none(),
+            },
+            // the magnitude of `i64::MIN` has no first root which fits, so don't take one
+            Operation::Pow if rhs.is_one() => Preimage::one(Some(result.clone())),
+            Operation::Pow => {
+                let Some(exponent) = rhs.to_u32() else {
+                    return Preimage::none();
+                };
+                let root = result.magnitude_root(exponent);
+                Preimage::Candidates([Some(root.clone()), Some(-root)])
+            }
+            Operation::Div => unreachable!("division is not invertible"),
+        }
+    }
+
+    /// Whether, for positive operands, this operation always produces a result at least 1.
+    fn preserves_positive(self) -> bool {
+        match self {
+            Operation::Add | Operation::Mul | Operation::Concat | Operation::Pow => true,
+            Operation::Sub | Operation::Xor | Operation::Div => false,
+        }
+    }
+
+    /// Whether, for positive operands, this operation's result is at least as great as each
+    /// operand.
+    fn never_decreases(self) -> bool {
+        match self {
+            Operation::Add | Operation::Mul | Operation::Concat => true,
+            Operation::Sub | Operation::Xor | Operation::Pow | Operation::Div => false,
+        }
+    }
+
+    /// Whether, for a non-negative left operand and a positive right operand, this operation's
+    /// result is at least their truncated quotient.
+    fn at_least_quotient(self) -> bool {
+        match self {
+            Operation::Add
+            | Operation::Mul
+            | Operation::Concat
+            | Operation::Pow
+            | Operation::Div => true,
+            Operation::Sub | Operation::Xor => false,
+        }
+    }
+
+    /// How tightly this operation binds under [`Order::Standard`]; greater binds tighter.
+    fn precedence(self, concat: ConcatPrecedence) -> u8 {
+        match self {
+            Operation::Xor => 1,
+            Operation::Add | Operation::Sub => 2,
+            Operation::Mul | Operation::Div => 3,
+            Operation::Pow => 4,
+            Operation::Concat => match concat {
+                ConcatPrecedence::Loosest => 0,
+                ConcatPrecedence::Additive => 2,
+                ConcatPrecedence::Multiplicative => 3,
+                ConcatPrecedence::Tightest => 5,
+            },
+        }
     }
 }
 
+/// An assignment of operations from an operator set to the gaps between an equation's values.
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Operations(Box<[Operation]>);
+struct Operations<'a> {
+    set: &'a [Operation],
+    /// Index into `set` of the operation in each gap
+    choices: Box<[usize]>,
+}
 
-impl Operations {
-    fn new(dimension: usize) -> Self {
-        Self(vec![Operation::default(); dimension].into())
+impl<'a> Operations<'a> {
+    fn new(set: &'a [Operation], dimension: usize) -> Self {
+        Self {
+            set,
+            choices: vec![0; dimension].into(),
+        }
     }
 
-    /// Continue to the next operation in sequence.
+    fn iter(&self) -> impl '_ + Iterator<Item = Operation> {
+        self.choices.iter().map(|&choice| self.set[choice])
+    }
+
+    /// Continue to the next assignment in sequence.
     ///
     /// Return `true` if this has overflowed back to the initial state.
     fn increment(&mut self) -> bool {
-        let mut idx = 0;
-        let mut incr = true;
-        while incr && idx < self.0.len() {
-            incr = self.0[idx].increment();
-            idx += 1;
+        for choice in self.choices.iter_mut() {
+            *choice += 1;
+            if *choice < self.set.len() {
+                return false;
+            }
+            *choice = 0;
         }
-        debug_assert_eq!(incr, *self == Self::new(self.0.len()));
-        incr
+        true
     }
 }
 
-impl Equation {
-    /// Produce all valid operand maps for this equation's values
-    fn operand_maps(&self) -> impl Iterator<Item = u32> {
-        let max = 2_u32.pow(self.values.0.len() as u32 - 1);
-        0..max
+/// Visit each assignment of `operations` which evaluates `values` left to right, after
+/// `value`, without failing.
+///
+/// The operation in each gap is written to `assignment`, starting at index `gap`; `visit`
+/// receives the whole of `assignment`.
+fn evaluations<N: Number>(
+    value: &N,
+    values: &[N],
+    operations: &[Operation],
+    assignment: &mut [Operation],
+    gap: usize,
+    visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some((next, rest)) = values.split_first() else {
+        return visit(assignment);
+    };
+    for &operation in operations {
+        if let Some(value) = operation.apply(value, next) {
+            assignment[gap] = operation;
+            evaluations(&value, rest, operations, assignment, gap + 1, visit)?;
+        }
     }
+    ControlFlow::Continue(())
+}
 
-    /// Evaluate the values of this equation according to the supplied operand map.
+/// Right-to-left search for assignments of operations which make an equation true.
+struct Solver<'a> {
+    operations: &'a [Operation],
+    /// Whether results less than 1 can be pruned
+    positive: bool,
+}
+
+impl<'a> Solver<'a> {
+    fn new<N: Number>(values: &[N], operations: &'a [Operation]) -> Self {
+        let positive = values.iter().all(Signed::is_positive)
+            && operations
+                .iter()
+                .all(|operation| operation.preserves_positive());
+        Self {
+            operations,
+            positive,
+        }
+    }
+
+    /// Each operation which can produce `result` from `last`, paired with the left operand
+    /// which it requires, or `None` if any left operand will do.
+    fn predecessors<'s, N: Number>(
+        &'s self,
+        result: &'s N,
+        last: &'s N,
+    ) -> impl 's + Iterator<Item = (Operation, Option<N>)> {
+        self.operations.iter().flat_map(move |&operation| {
+            let preimage = operation.undo(result, last);
+            let any = matches!(preimage, Preimage::Any);
+            any.then_some((operation, None)).into_iter().chain(
+                preimage
+                    .candidates()
+                    .filter(move |lhs| operation.apply(lhs, last).as_ref() == Some(result))
+                    .map(move |lhs| (operation, Some(lhs))),
+            )
+        })
+    }
+
+    /// Visit each assignment which makes `values` evaluate to `result`, until `visit` breaks.
     ///
-    /// In the map, `0` at a given index indicates addition, and `1` indicates multiplication.
-    fn evaluate(&self, operand_map: u32) -> Option<i64> {
-        assert!(
-            self.values.0.len() <= 33,
-            "this function can't handle long terms"
-        );
-        let mut value = self.values.0[0];
-        for (idx, v) in self.values.0[1..].iter().copied().enumerate() {
-            if operand_map & (1 << idx) == 0 {
-                value = value.checked_add(v)?;
-            } else {
-                value = value.checked_mul(v)?;
+    /// `values` is a prefix of the equation's values. The operations of the gaps between them
+    /// are written to the start of `assignment`, which holds every gap of the equation and
+    /// whose later gaps are already assigned; `visit` receives the whole of it.
+    fn walk<N: Number>(
+        &self,
+        values: &[N],
+        result: &N,
+        assignment: &mut [Operation],
+        visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if self.positive && !result.is_positive() {
+            return ControlFlow::Continue(());
+        }
+        let [preceding @ .., last] = values else {
+            return ControlFlow::Continue(());
+        };
+        let Some((first, rest)) = preceding.split_first() else {
+            return match last == result {
+                true => visit(assignment),
+                false => ControlFlow::Continue(()),
+            };
+        };
+        let gap = preceding.len() - 1;
+        for (operation, lhs) in self.predecessors(result, last) {
+            assignment[gap] = operation;
+            match lhs {
+                Some(lhs) => self.walk(preceding, &lhs, assignment, visit)?,
+                None => evaluations(first, rest, self.operations, assignment, 0, visit)?,
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Left-to-right search for assignments of operations which make an equation true, for
+/// operation sets which [`Solver`] cannot undo.
+///
+/// This searches forwards. When every value is positive and every operation's result is at
+/// least its operands' quotient, the running value can shrink by at most the product of the
+/// values still to come, so branches which can no longer come back down to the test value are
+/// pruned.
+struct ForwardSolver<'a, N> {
+    operations: &'a [Operation],
+    test_value: &'a N,
+    /// For each value, the product of every value after it, if branches can be pruned and the
+    /// product is representable
+    shrinkage: Vec<Option<N>>,
+}
+
+impl<'a, N: Number> ForwardSolver<'a, N> {
+    fn new(equation: &'a Equation<N>, operations: &'a [Operation]) -> Self {
+        let values = &equation.values.0;
+        let prunable = values.iter().all(Signed::is_positive)
+            && operations
+                .iter()
+                .all(|operation| operation.at_least_quotient());
+        let mut shrinkage = Vec::new();
+        if prunable {
+            let mut product = Some(N::one());
+            for value in values.iter().rev() {
+                shrinkage.push(product.clone());
+                product = product.and_then(|product| product.checked_mul(value));
+            }
+            shrinkage.reverse();
+        }
+        Self {
+            operations,
+            test_value: &equation.test_value,
+            shrinkage,
+        }
+    }
+
+    /// Call `visit` with each assignment which makes the equation true, until it breaks.
+    fn run(
+        &self,
+        values: &[N],
+        visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let Some(first) = values.first() else {
+            return ControlFlow::Continue(());
+        };
+        let mut assignment = vec![Operation::default(); values.len() - 1];
+        self.walk(first.clone(), values, 0, &mut assignment, visit)
+    }
+
+    /// `value` is the result of evaluating `values` up to and including index `idx`.
+    fn walk(
+        &self,
+        value: N,
+        values: &[N],
+        idx: usize,
+        assignment: &mut [Operation],
+        visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if let Some(Some(product)) = self.shrinkage.get(idx) {
+            if value
+                .checked_div(product)
+                .is_some_and(|least| least > *self.test_value)
+            {
+                return ControlFlow::Continue(());
             }
         }
-        Some(value)
+        let Some(next) = values.get(idx + 1) else {
+            return match value == *self.test_value {
+                true => visit(assignment),
+                false => ControlFlow::Continue(()),
+            };
+        };
+        for &operation in self.operations {
+            if let Some(value) = operation.apply(&value, next) {
+                assignment[idx] = operation;
+                self.walk(value, values, idx + 1, assignment, visit)?;
+            }
+        }
+        ControlFlow::Continue(())
     }
+}
+
+/// Left operands awaiting their right operands, with their operations, in order of strictly
+/// increasing precedence.
+type Pending<N> = Vec<(N, Operation)>;
 
-    /// Evaluate the values of this equation according to the supplied operands list.
-    fn evaluate_operands(&self, operands: &Operations) -> Option<i64> {
+/// Apply each pending operation which binds at least as tightly as `precedence`, finishing
+/// with `value` as the rightmost operand.
+fn reduce<N: Number>(
+    pending: &mut Pending<N>,
+    mut value: N,
+    precedence: u8,
+    concat: ConcatPrecedence,
+) -> Option<N> {
+    while pending
+        .last()
+        .is_some_and(|(_, operation)| operation.precedence(concat) >= precedence)
+    {
+        let (lhs, operation) = pending.pop()?;
+        value = operation.apply(&lhs, &value)?;
+    }
+    Some(value)
+}
+
+/// Depth-first search for assignments of operations which make an equation true under
+/// [`Order::Standard`].
+///
+/// Undoing the final operation no longer isolates the preceding terms, so this searches
+/// forwards. When every value is positive and no operation can decrease its operands, partial
+/// values greater than the test value are pruned.
+struct PrecedenceSearch<'a, N> {
+    operations: &'a [Operation],
+    concat: ConcatPrecedence,
+    test_value: &'a N,
+    /// Whether partial values greater than the test value can be pruned
+    monotone: bool,
+}
+
+impl<'a, N: Number> PrecedenceSearch<'a, N> {
+    fn new(
+        equation: &'a Equation<N>,
+        operations: &'a [Operation],
+        concat: ConcatPrecedence,
+    ) -> Self {
+        let monotone = equation.values.0.iter().all(Signed::is_positive)
+            && operations
+                .iter()
+                .all(|operation| operation.never_decreases());
+        Self {
+            operations,
+            concat,
+            test_value: &equation.test_value,
+            monotone,
+        }
+    }
+
+    /// Call `visit` with each assignment which makes the equation true, until it breaks.
+    fn run(
+        &self,
+        values: &[N],
+        visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let Some((first, rest)) = values.split_first() else {
+            return ControlFlow::Continue(());
+        };
+        let mut assignment = Vec::with_capacity(rest.len());
+        self.search(Vec::new(), first.clone(), rest, &mut assignment, visit)
+    }
+
+    fn search(
+        &self,
+        pending: Pending<N>,
+        value: N,
+        values: &[N],
+        assignment: &mut Vec<Operation>,
+        visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if self.monotone
+            && (value > *self.test_value || pending.iter().any(|(lhs, _)| lhs > self.test_value))
+        {
+            return ControlFlow::Continue(());
+        }
+        let Some((next, rest)) = values.split_first() else {
+            let mut pending = pending;
+            return match reduce(&mut pending, value, 0, self.concat) {
+                Some(value) if value == *self.test_value => visit(assignment),
+                _ => ControlFlow::Continue(()),
+            };
+        };
+        for &operation in self.operations {
+            let mut pending = pending.clone();
+            let precedence = operation.precedence(self.concat);
+            let Some(value) = reduce(&mut pending, value.clone(), precedence, self.concat) else {
+                continue;
+            };
+            pending.push((value, operation));
+            assignment.push(operation);
+            let flow = self.search(pending, next.clone(), rest, assignment, visit);
+            assignment.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// An equation together with operations which make it true.
+#[derive(Debug)]
+pub struct Witness<'a, N: Number = i64> {
+    equation: &'a Equation<N>,
+    operations: Vec<Operation>,
+}
+
+impl<N: Number> Witness<'_, N> {
+    /// The operations between each pair of values, from left to right.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+}
+
+impl<N: Number> fmt::Display for Witness<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} =", self.equation.test_value)?;
+        let mut operations = self.operations.iter();
+        for (idx, value) in self.equation.values.0.iter().enumerate() {
+            if idx > 0 {
+                if let Some(operation) = operations.next() {
+                    write!(f, " {operation}")?;
+                }
+            }
+            write!(f, " {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<N: Number> Equation<N> {
+    /// Evaluate the values of this equation according to the supplied operations list.
+    fn evaluate_operands(&self, operations: &Operations, order: Order) -> Option<N> {
         debug_assert_eq!(
-            operands.0.len(),
+            operations.choices.len(),
             self.values.0.len() - 1,
-            "operands must interleave between equation terms"
+            "operations must interleave between equation terms"
         );
-        let mut value = self.values.0[0];
-        for (operand, v) in operands.0.iter().zip(self.values.0.iter().skip(1)) {
-            match *operand {
-                Operation::Add => value = value.checked_add(*v)?,
-                Operation::Mul => value = value.checked_mul(*v)?,
-                Operation::Concat => {
-                    let n_digits = v.ilog10() + 1;
-                    value = value.checked_mul(10_u32.pow(n_digits) as i64)?;
-                    value = value.checked_add(*v)?;
+        let mut value = self.values.0[0].clone();
+        let terms = operations.iter().zip(self.values.0.iter().skip(1));
+        match order {
+            Order::LeftToRight => {
+                for (operation, v) in terms {
+                    value = operation.apply(&value, v)?;
+                }
+                Some(value)
+            }
+            Order::Standard(concat) => {
+                let mut pending = Pending::new();
+                for (operation, v) in terms {
+                    let lhs = reduce(&mut pending, value, operation.precedence(concat), concat)?;
+                    pending.push((lhs, operation));
+                    value = v.clone();
                 }
+                reduce(&mut pending, value, 0, concat)
             }
         }
-        Some(value)
     }
 
-    fn can_evaluate_true(&self) -> bool {
-        self.operand_maps()
-            .any(|map| self.evaluate(map) == Some(self.test_value))
-    }
-
-    fn can_evaluate_true_with_concat(&self) -> bool {
-        let mut operands = Operations::new(self.values.0.len() - 1);
+    /// Whether some combination of `operations` makes this equation true,
+    /// by trying every combination.
+    ///
+    /// Nothing is pruned, so the tests check [`Self::solvable`] against this, and the benchmarks
+    /// measure what the solver's pruning is worth.
+    pub fn brute_force(&self, operations: &[Operation], order: Order) -> bool {
+        let mut assignment = Operations::new(operations, self.values.0.len() - 1);
         let mut overflowed = false;
 
         while !overflowed {
-            if self.evaluate_operands(&operands) == Some(self.test_value) {
+            if self.evaluate_operands(&assignment, order).as_ref() == Some(&self.test_value) {
                 return true;
             }
-            overflowed = operands.increment();
+            overflowed = assignment.increment();
         }
         false
     }
+
+    /// Visit each combination of `operations` which makes this equation true, until `visit`
+    /// breaks.
+    fn search(
+        &self,
+        operations: &[Operation],
+        order: Order,
+        visit: &mut dyn FnMut(&[Operation]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let values = &self.values.0;
+        match order {
+            Order::LeftToRight if !operations.iter().all(|operation| operation.invertible()) => {
+                ForwardSolver::new(self, operations).run(values, visit)
+            }
+            Order::LeftToRight => {
+                let mut assignment = vec![Operation::default(); values.len().saturating_sub(1)];
+                Solver::new(values, operations).walk(
+                    values,
+                    &self.test_value,
+                    &mut assignment,
+                    visit,
+                )
+            }
+            Order::Standard(concat) => {
+                PrecedenceSearch::new(self, operations, concat).run(values, visit)
+            }
+        }
+    }
+
+    /// Whether some combination of `operations` makes this equation true.
+    ///
+    /// Evaluating left to right, this works backwards from the test value: each operation is
+    /// undone against the final value, producing the value which the preceding terms must
+    /// reach. Operations which cannot be undone prune the search: for example, multiplication
+    /// requires the result to be divisible by the final value, and concatenation requires it to
+    /// end with the final value's digits.
+    ///
+    /// When every value is positive and every operation preserves positivity, results less than
+    /// 1 are pruned as well.
+    ///
+    /// Undoing a truncating division admits as many left operands as the magnitude of the
+    /// divisor, so operation sets which include division are searched forwards instead. When
+    /// every value is positive and there is no subtraction or exclusive or, branches whose
+    /// running value is too large to be divided back down to the test value are pruned.
+    ///
+    /// Under [`Order::Standard`], the search runs forwards instead, pruning partial values
+    /// greater than the test value when every value is positive and no operation can decrease
+    /// its operands.
+    ///
+    /// There is no limit on the number of terms.
+    pub fn solvable(&self, operations: &[Operation], order: Order) -> bool {
+        self.search(operations, order, &mut |_| ControlFlow::Break(()))
+            .is_break()
+    }
+
+    /// One combination of `operations` which makes this equation true, if any exists.
+    pub fn witness(&self, operations: &[Operation], order: Order) -> Option<Witness<'_, N>> {
+        let mut witness = None;
+        let _ = self.search(operations, order, &mut |assignment| {
+            witness = Some(assignment.to_vec());
+            ControlFlow::Break(())
+        });
+        witness.map(|operations| Witness {
+            equation: self,
+            operations,
+        })
+    }
+
+    /// How many distinct combinations of `operations` make this equation true.
+    ///
+    /// Saturates at `u64::MAX`.
+    pub fn count_solutions(&self, operations: &[Operation], order: Order) -> u64 {
+        let mut count = 0_u64;
+        let _ = self.search(operations, order, &mut |_| {
+            count = count.saturating_add(1);
+            ControlFlow::Continue(())
+        });
+        count
+    }
+}
+
+/// How values are represented while evaluating equations.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    /// 64-bit integers: fast, but equations whose evaluation overflows are unsolvable
+    #[default]
+    Fixed,
+    /// Arbitrary-precision integers
+    Arbitrary,
+}
+
+/// Solver settings which can be loaded from a TOML file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolverConfig {
+    /// Operations which may appear between values
+    pub operators: Vec<Operation>,
+    pub precision: Precision,
+    /// Evaluate by operator precedence instead of strictly left to right
+    pub standard_precedence: bool,
+    pub concat_precedence: ConcatPrecedence,
+}
+
+impl SolverConfig {
+    /// Load settings from a TOML file.
+    ///
+    /// Unspecified fields take their default values.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let data = std::fs::read_to_string(path)?;
+        let config = toml::from_str::<Self>(&data)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn order(&self) -> Order {
+        match self.standard_precedence {
+            false => Order::LeftToRight,
+            true => Order::Standard(self.concat_precedence),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        for (idx, operator) in self.operators.iter().enumerate() {
+            if self.operators[..idx].contains(operator) {
+                return Err(Error::InvalidConfig(format!(
+                    "operator {operator} appears more than once"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sum the test values of the equations which some combination of `operations` makes true.
+///
+/// Under [`Order::Standard`], the left-to-right total follows for comparison, from the same
+/// parse of the input.
+fn calibration<N: Number>(
+    input: &Path,
+    operations: &[Operation],
+    order: Order,
+) -> Result<(N, Option<N>), Error> {
+    let equations = parse::<Equation<N>>(input)?.collect::<Vec<_>>();
+    let total = |order| {
+        equations
+            .iter()
+            .filter(|equation| equation.solvable(operations, order))
+            .try_fold(N::zero(), |sum, equation| {
+                sum.checked_add(&equation.test_value).ok_or(Error::Overflow)
+            })
+    };
+    let left_to_right = match order {
+        Order::LeftToRight => None,
+        Order::Standard(_) => Some(total(Order::LeftToRight)?),
+    };
+    Ok((total(order)?, left_to_right))
+}
+
+/// Describe a calibration total, with the left-to-right total for comparison if there is one.
+fn describe_calibration<N: fmt::Display>((total, left_to_right): (N, Option<N>)) -> String {
+    match left_to_right {
+        None => total.to_string(),
+        Some(left_to_right) => format!("{total} (left to right: {left_to_right})"),
+    }
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let calibration = parse::<Equation>(input)?
-        .filter(Equation::can_evaluate_true)
-        .map(|equation| equation.test_value)
-        .sum::<i64>();
-    println!("sum of calibration: {calibration}");
+impl Precision {
+    /// Sum the test values of the equations which some combination of `operations` makes true,
+    /// evaluating at this precision.
+    ///
+    /// Under [`Order::Standard`], the left-to-right total follows for comparison.
+    fn calibration(
+        self,
+        input: &Path,
+        operations: &[Operation],
+        order: Order,
+    ) -> Result<String, Error> {
+        match self {
+            Precision::Fixed => {
+                calibration::<i64>(input, operations, order).map(describe_calibration)
+            }
+            Precision::Arbitrary => {
+                calibration::<BigInt>(input, operations, order).map(describe_calibration)
+            }
+        }
+    }
+}
+
+pub fn part1(input: &Path, precision: Precision, order: Order) -> Result<(), Error> {
+    let calibration = precision.calibration(input, Operation::PART1, order)?;
+    let annotation = order.annotation();
+    println!("sum of calibration{annotation}: {calibration}");
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
-    let calibration = parse::<Equation>(input)?
-        .filter(Equation::can_evaluate_true_with_concat)
-        .map(|equation| equation.test_value)
-        .sum::<i64>();
-    println!("sum of calibration with concat: {calibration}");
+pub fn part2(input: &Path, precision: Precision, order: Order) -> Result<(), Error> {
+    let calibration = precision.calibration(input, Operation::PART2, order)?;
+    let annotation = order.annotation();
+    println!("sum of calibration with concat{annotation}: {calibration}");
     Ok(())
 }
 
+/// Sum the test values of the equations which some combination of `operations` makes true.
+pub fn calibrate(
+    input: &Path,
+    operations: &[Operation],
+    precision: Precision,
+    order: Order,
+) -> Result<(), Error> {
+    if operations.is_empty() {
+        return Err(Error::InvalidConfig("no operators".into()));
+    }
+    let calibration = precision.calibration(input, operations, order)?;
+    let operators = operations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let annotation = order.annotation();
+    println!("sum of calibration with operators {operators}{annotation}: {calibration}");
+    Ok(())
+}
+
+fn print_witnesses<N: Number>(
+    input: &Path,
+    operations: &[Operation],
+    count: bool,
+    order: Order,
+) -> Result<(), Error> {
+    let mut ambiguous = 0_usize;
+    for equation in parse::<Equation<N>>(input)? {
+        let Some(witness) = equation.witness(operations, order) else {
+            continue;
+        };
+        if count {
+            let solutions = equation.count_solutions(operations, order);
+            if solutions > 1 {
+                ambiguous += 1;
+            }
+            let plural = if solutions == 1 { "" } else { "s" };
+            println!("{witness} ({solutions} solution{plural})");
+        } else {
+            println!("{witness}");
+        }
+    }
+    if count {
+        println!("ambiguous equations: {ambiguous}");
+    }
+    Ok(())
+}
+
+/// Print one expression which makes each solvable equation true.
+///
+/// When `count` is set, also print how many combinations of `operations` make each equation
+/// true, and how many equations are ambiguous.
+pub fn witnesses(
+    input: &Path,
+    operations: &[Operation],
+    count: bool,
+    precision: Precision,
+    order: Order,
+) -> Result<(), Error> {
+    if operations.is_empty() {
+        return Err(Error::InvalidConfig("no operators".into()));
+    }
+    match precision {
+        Precision::Fixed => print_witnesses::<i64>(input, operations, count, order),
+        Precision::Arbitrary => print_witnesses::<BigInt>(input, operations, count, order),
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("not a number")]
     NaN(#[from] std::num::ParseIntError),
+    #[error("not a number")]
+    BigNaN(#[from] num_bigint::ParseBigIntError),
+    #[error("calibration total overflows; try arbitrary precision")]
+    Overflow,
+    #[error("parsing config")]
+    ConfigFormat(#[from] toml::de::Error),
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
     #[error("no solution found")]
     NoSolution,
 }
 
 #[cfg(test)]
 mod tests {
+    mod part1 {
+        use crate::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case(190, &[10, 19], true)]
+        #[case(3267, &[81, 40, 27], true)]
+        #[case(83, &[17, 5], false)]
+        #[case(156, &[15, 6], false)]
+        #[case(7290, &[6, 8, 6, 15], false)]
+        #[case(161011, &[16, 10, 13], false)]
+        #[case(192, &[17, 8, 14], false)]
+        #[case(21037, &[9, 7, 18, 13], false)]
+        #[case(292, &[11, 6, 16, 20], true)]
+        fn examples(#[case] test_value: i64, #[case] values: &[i64], #[case] expect: bool) {
+            let equation = Equation {
+                test_value,
+                values: Values(values.into()),
+            };
+            assert_eq!(
+                equation.brute_force(Operation::PART1, Order::LeftToRight),
+                expect
+            );
+            assert_eq!(
+                equation.solvable(Operation::PART1, Order::LeftToRight),
+                expect
+            );
+        }
+    }
+
+    mod witness {
+        use crate::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case(190, &[10, 19], Operation::PART1, Some("190 = 10 * 19"), 1)]
+        #[case(3267, &[81, 40, 27], Operation::PART1, Some("3267 = 81 * 40 + 27"), 2)]
+        #[case(83, &[17, 5], Operation::PART1, None, 0)]
+        #[case(292, &[11, 6, 16, 20], Operation::PART1, Some("292 = 11 + 6 * 16 + 20"), 1)]
+        #[case(156, &[15, 6], Operation::PART2, Some("156 = 15 || 6"), 1)]
+        #[case(7290, &[6, 8, 6, 15], Operation::PART2, Some("7290 = 6 * 8 || 6 * 15"), 1)]
+        #[case(192, &[17, 8, 14], Operation::PART2, Some("192 = 17 || 8 + 14"), 1)]
+        fn examples(
+            #[case] test_value: i64,
+            #[case] values: &[i64],
+            #[case] operations: &[Operation],
+            #[case] expect: Option<&str>,
+            #[case] solutions: u64,
+        ) {
+            let equation = Equation {
+                test_value,
+                values: Values(values.into()),
+            };
+            assert_eq!(
+                equation
+                    .witness(operations, Order::LeftToRight)
+                    .map(|witness| witness.to_string())
+                    .as_deref(),
+                expect
+            );
+            assert_eq!(
+                equation.count_solutions(operations, Order::LeftToRight),
+                solutions
+            );
+        }
+    }
+
     mod part2 {
         use crate::*;
         use rstest::rstest;
@@ -184,7 +1118,52 @@ mod tests {
                 test_value,
                 values: Values(values.into()),
             };
-            assert_eq!(equation.can_evaluate_true_with_concat(), expect);
+            assert_eq!(
+                equation.brute_force(Operation::PART2, Order::LeftToRight),
+                expect
+            );
+            assert_eq!(
+                equation.solvable(Operation::PART2, Order::LeftToRight),
+                expect
+            );
+        }
+
+        #[test]
+        fn solver_has_no_term_limit() {
+            // 40 terms: 1 * 1 * ... * 1 + 1 + ... + 1
+            let equation = Equation {
+                test_value: 20,
+                values: Values(vec![1; 40]),
+            };
+            assert!(equation.solvable(Operation::PART1, Order::LeftToRight));
+            assert!(!Equation {
+                test_value: 0,
+                values: Values(vec![1; 40]),
+            }
+            .solvable(Operation::PART2, Order::LeftToRight));
+        }
+
+        #[rstest]
+        #[case(0, &[5, 0], true)]
+        #[case(0, &[0, 7, 0], true)]
+        #[case(50, &[5, 0], true)]
+        #[case(5, &[5, 0], true)]
+        #[case(58, &[5, 0, 7], false)]
+        #[case(507, &[5, 0, 7], true)]
+        #[case(7, &[0, 0, 7], true)]
+        fn zero_operands(#[case] test_value: i64, #[case] values: &[i64], #[case] expect: bool) {
+            let equation = Equation {
+                test_value,
+                values: Values(values.into()),
+            };
+            assert_eq!(
+                equation.brute_force(Operation::PART2, Order::LeftToRight),
+                expect
+            );
+            assert_eq!(
+                equation.solvable(Operation::PART2, Order::LeftToRight),
+                expect
+            );
         }
 
         #[rstest]
@@ -197,8 +1176,434 @@ mod tests {
                 test_value,
                 values: Values(values.into()),
             };
-            let operations = Operations([Operation::Concat].into());
-            assert_eq!(equation.evaluate_operands(&operations), Some(test_value));
+            let operations = Operations::new(&[Operation::Concat], 1);
+            assert_eq!(
+                equation.evaluate_operands(&operations, Order::LeftToRight),
+                Some(test_value)
+            );
+        }
+    }
+
+    mod precedence {
+        use crate::*;
+        use rstest::rstest;
+
+        const STANDARD: Order = Order::Standard(ConcatPrecedence::Tightest);
+
+        #[rstest]
+        #[case(190, &[10, 19], true)]
+        #[case(3267, &[81, 40, 27], true)]
+        #[case(83, &[17, 5], false)]
+        #[case(1161, &[81, 40, 27], true)]
+        // 11 + 6 * 16 + 20 is 127 when multiplication binds tighter
+        #[case(292, &[11, 6, 16, 20], false)]
+        #[case(127, &[11, 6, 16, 20], true)]
+        fn examples(#[case] test_value: i64, #[case] values: &[i64], #[case] expect: bool) {
+            let equation = Equation {
+                test_value,
+                values: Values(values.into()),
+            };
+            assert_eq!(equation.brute_force(Operation::PART1, STANDARD), expect);
+            assert_eq!(equation.solvable(Operation::PART1, STANDARD), expect);
+        }
+
+        #[rstest]
+        #[case("68: 2 3 4", ConcatPrecedence::Tightest, "68 = 2 * 3 || 4")]
+        #[case("64: 2 3 4", ConcatPrecedence::Multiplicative, "64 = 2 * 3 || 4")]
+        #[case("312: 1 2 3 4", ConcatPrecedence::Additive, "312 = 1 + 2 || 3 * 4")]
+        #[case("11: 1 2 3", ConcatPrecedence::Loosest, "11 = 1 || 2 ^ 3")]
+        fn concat_precedence(
+            #[case] equation: &str,
+            #[case] concat: ConcatPrecedence,
+            #[case] expect: &str,
+        ) {
+            const OPERATIONS: &[Operation] = &[
+                Operation::Add,
+                Operation::Mul,
+                Operation::Concat,
+                Operation::Xor,
+            ];
+            let equation = equation.parse::<Equation>().unwrap();
+            let order = Order::Standard(concat);
+            let witness = equation.witness(OPERATIONS, order).unwrap();
+            assert_eq!(witness.to_string(), expect);
+        }
+
+        #[rstest]
+        fn solver_agrees_with_brute_force(
+            #[values(
+                Operation::PART2,
+                &[Operation::Sub, Operation::Mul, Operation::Concat],
+                &[Operation::Xor, Operation::Pow, Operation::Div, Operation::Add],
+            )]
+            operations: &[Operation],
+            #[values(
+                ConcatPrecedence::Tightest,
+                ConcatPrecedence::Multiplicative,
+                ConcatPrecedence::Additive,
+                ConcatPrecedence::Loosest
+            )]
+            concat: ConcatPrecedence,
+        ) {
+            let order = Order::Standard(concat);
+            for a in 1..=4 {
+                for b in -1..=3 {
+                    for c in 1..=3 {
+                        for test_value in -10..=60 {
+                            let equation = Equation {
+                                test_value,
+                                values: Values(vec![a, b, c, 2]),
+                            };
+                            let mut assignment =
+                                Operations::new(operations, equation.values.0.len() - 1);
+                            let mut expect = 0;
+                            loop {
+                                if equation.evaluate_operands(&assignment, order)
+                                    == Some(test_value)
+                                {
+                                    expect += 1;
+                                }
+                                if assignment.increment() {
+                                    break;
+                                }
+                            }
+                            let context = format!("{test_value}: {a} {b} {c} 2");
+                            assert_eq!(
+                                equation.solvable(operations, order),
+                                expect > 0,
+                                "{context}"
+                            );
+                            assert_eq!(
+                                equation.count_solutions(operations, order),
+                                expect,
+                                "{context}"
+                            );
+                            if let Some(witness) = equation.witness(operations, order) {
+                                let assignment = Operations {
+                                    set: witness.operations(),
+                                    choices: (0..witness.operations().len()).collect(),
+                                };
+                                assert_eq!(
+                                    equation.evaluate_operands(&assignment, order),
+                                    Some(test_value),
+                                    "{witness}"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn config() {
+            let config = toml::from_str::<SolverConfig>(
+                "standard_precedence = true\nconcat_precedence = \"additive\"",
+            )
+            .unwrap();
+            assert_eq!(config.order(), Order::Standard(ConcatPrecedence::Additive));
+            assert_eq!(SolverConfig::default().order(), Order::LeftToRight);
+        }
+    }
+
+    mod precision {
+        use crate::*;
+        use rstest::rstest;
+
+        #[rstest]
+        // the product overflows `i64` before the division brings it back in range
+        #[case(
+            "3000000000000000005: 3000000000000000005 10 10",
+            &[Operation::Mul, Operation::Div],
+            "3000000000000000005 = 3000000000000000005 * 10 / 10",
+        )]
+        #[case(
+            "1267650600228229401496703205376: 2 100",
+            &[Operation::Pow],
+            "1267650600228229401496703205376 = 2 ** 100",
+        )]
+        #[case(
+            "12345678901234567890123: 1234567890 1234567890 123",
+            Operation::PART2,
+            "12345678901234567890123 = 1234567890 || 1234567890 || 123"
+        )]
+        fn arbitrary_precision(
+            #[case] equation: &str,
+            #[case] operations: &[Operation],
+            #[case] expect: &str,
+        ) {
+            let big = equation.parse::<Equation<BigInt>>().unwrap();
+            assert!(big.solvable(operations, Order::LeftToRight));
+            assert_eq!(
+                big.witness(operations, Order::LeftToRight)
+                    .unwrap()
+                    .to_string(),
+                expect
+            );
+            assert_eq!(big.count_solutions(operations, Order::LeftToRight), 1);
+            if let Ok(fixed) = equation.parse::<Equation>() {
+                assert!(!fixed.solvable(operations, Order::LeftToRight));
+            }
+        }
+
+        #[rstest]
+        #[case(
+            "1: 10000000000000000000000000000000000000000 10000000000000000000000000000000000000000",
+            &[Operation::Div],
+            1
+        )]
+        #[case(
+            "3: 100000000000000000000000000000000000000000 33333333333333333333333333333333333333333",
+            &[Operation::Add, Operation::Div],
+            1
+        )]
+        #[case(
+            "2: 100000000000000000000 100000000000000000000 100000000000000000000 100000000000000000000 50000000000000000000000000000000000000000",
+            &[Operation::Mul, Operation::Div, Operation::Sub],
+            0
+        )]
+        fn large_divisors(
+            #[case] equation: &str,
+            #[case] operations: &[Operation],
+            #[case] solutions: u64,
+        ) {
+            let equation = equation.parse::<Equation<BigInt>>().unwrap();
+            assert_eq!(
+                equation.count_solutions(operations, Order::LeftToRight),
+                solutions
+            );
+            assert_eq!(
+                equation.solvable(operations, Order::LeftToRight),
+                solutions > 0
+            );
+        }
+
+        #[test]
+        fn huge_power_is_refused() {
+            let equation = "1: 10 1000000000".parse::<Equation<BigInt>>().unwrap();
+            assert!(!equation.solvable(&[Operation::Pow], Order::LeftToRight));
+        }
+
+        #[test]
+        fn config() {
+            let config = toml::from_str::<SolverConfig>(r#"precision = "arbitrary""#).unwrap();
+            assert_eq!(config.precision, Precision::Arbitrary);
+            assert_eq!(SolverConfig::default().precision, Precision::Fixed);
+        }
+    }
+
+    mod operators {
+        use crate::*;
+        use rstest::rstest;
+
+        const ALL: &[Operation] = &[
+            Operation::Add,
+            Operation::Mul,
+            Operation::Concat,
+            Operation::Sub,
+            Operation::Xor,
+            Operation::Pow,
+            Operation::Div,
+        ];
+
+        #[test]
+        fn undo_finds_every_lhs() {
+            // extreme operands included: the magnitude of `i64::MIN` has no first root in range
+            let lhss = (-30..=30).chain([i64::MIN, i64::MIN + 1, i64::MAX]);
+            for &operation in ALL.iter().filter(|operation| operation.invertible()) {
+                for lhs in lhss.clone() {
+                    for rhs in -6..=12 {
+                        let Some(result) = operation.apply(&lhs, &rhs) else {
+                            continue;
+                        };
+                        let found = match operation.undo(&result, &rhs) {
+                            Preimage::Any => true,
+                            Preimage::Candidates(candidates) => candidates.contains(&Some(lhs)),
+                        };
+                        assert!(found, "{lhs} {operation} {rhs} = {result}");
+                    }
+                }
+            }
+        }
+
+        #[rstest]
+        #[case("-9223372036854775808: -9223372036854775808 1", 1)]
+        #[case("9223372036854775807: 9223372036854775807 1", 1)]
+        #[case("-9223372036854775808: -2 63", 1)]
+        #[case("-9223372036854775808: -9223372036854775808 2", 0)]
+        fn extreme_powers(#[case] equation: &str, #[case] solutions: u64) {
+            let equation = equation.parse::<Equation>().unwrap();
+            let operations = &[Operation::Pow];
+            assert_eq!(
+                equation.count_solutions(operations, Order::LeftToRight),
+                solutions
+            );
+            assert_eq!(
+                equation.brute_force(operations, Order::LeftToRight),
+                solutions > 0
+            );
+        }
+
+        #[rstest]
+        #[case(12, 0, 120)]
+        #[case(0, 0, 0)]
+        #[case(0, 7, 7)]
+        #[case(12, -3, 117)]
+        #[case(-12, 3, -117)]
+        #[case(5, -10, 490)]
+        fn concat_zero_and_negative(#[case] lhs: i64, #[case] rhs: i64, #[case] expect: i64) {
+            assert_eq!(Operation::Concat.apply(&lhs, &rhs), Some(expect));
+            assert_eq!(
+                Operation::Concat.apply(&BigInt::from(lhs), &BigInt::from(rhs)),
+                Some(BigInt::from(expect))
+            );
+        }
+
+        #[rstest]
+        #[case(&[Operation::Sub, Operation::Mul])]
+        #[case(&[Operation::Add, Operation::Xor])]
+        #[case(&[Operation::Add, Operation::Pow])]
+        #[case(&[Operation::Mul, Operation::Div])]
+        #[case(&[Operation::Sub, Operation::Div, Operation::Xor])]
+        #[case(&[Operation::Concat, Operation::Sub])]
+        #[case(&[Operation::Concat, Operation::Mul])]
+        fn solver_agrees_with_brute_force(#[case] operations: &[Operation]) {
+            for a in 0..=6 {
+                for b in -2..=4 {
+                    for c in -1..=3 {
+                        for test_value in -12..=40 {
+                            let equation = Equation {
+                                test_value,
+                                values: Values(vec![a, b, c]),
+                            };
+                            let expect = equation.brute_force(operations, Order::LeftToRight);
+                            assert_eq!(
+                                equation.solvable(operations, Order::LeftToRight),
+                                expect,
+                                "{test_value}: {a} {b} {c}"
+                            );
+                            let equation = Equation {
+                                test_value: BigInt::from(test_value),
+                                values: Values(vec![a.into(), b.into(), c.into()]),
+                            };
+                            assert_eq!(
+                                equation.solvable(operations, Order::LeftToRight),
+                                expect,
+                                "{test_value}: {a} {b} {c} with arbitrary precision"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        /// Count the combinations of `operations` which make `equation` true by trying each.
+        fn count_by_brute_force(equation: &Equation, operations: &[Operation]) -> u64 {
+            let mut assignment = Operations::new(operations, equation.values.0.len() - 1);
+            let mut count = 0;
+            loop {
+                if equation.evaluate_operands(&assignment, Order::LeftToRight)
+                    == Some(equation.test_value)
+                {
+                    count += 1;
+                }
+                if assignment.increment() {
+                    return count;
+                }
+            }
+        }
+
+        #[rstest]
+        #[case(&[Operation::Add, Operation::Mul])]
+        #[case(&[Operation::Sub, Operation::Mul, Operation::Pow])]
+        #[case(&[Operation::Mul, Operation::Div, Operation::Xor])]
+        fn witnesses_agree_with_brute_force(#[case] operations: &[Operation]) {
+            for a in 0..=4 {
+                for b in -2..=3 {
+                    for c in 0..=3 {
+                        for test_value in -8..=30 {
+                            let equation = Equation {
+                                test_value,
+                                values: Values(vec![a, b, c, 2]),
+                            };
+                            let expect = count_by_brute_force(&equation, operations);
+                            assert_eq!(
+                                equation.count_solutions(operations, Order::LeftToRight),
+                                expect,
+                                "{test_value}: {a} {b} {c} 2"
+                            );
+                            let witness = equation.witness(operations, Order::LeftToRight);
+                            assert_eq!(witness.is_some(), expect > 0);
+                            if let Some(witness) = witness {
+                                let assignment = Operations {
+                                    set: witness.operations(),
+                                    choices: (0..witness.operations().len()).collect(),
+                                };
+                                assert_eq!(
+                                    equation.evaluate_operands(&assignment, Order::LeftToRight),
+                                    Some(test_value),
+                                    "{witness}"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        #[rstest]
+        #[case("5: 1000000000 1000000000", &[Operation::Div], 0)]
+        #[case("1: 1000000000 1000000000", &[Operation::Div], 1)]
+        #[case("3: 9223372036854775807 3074457345618258602", &[Operation::Div], 1)]
+        #[case(
+            "1000: 1000000000 1000000000 1000000000 1000000000 1000000000 1000000000 1000",
+            &[Operation::Mul, Operation::Div],
+            4
+        )]
+        #[case(
+            "7: 1000000000 999999999 1000000000 999999999 1000000000 999999999 1000000000",
+            &[Operation::Sub, Operation::Div, Operation::Xor],
+            0
+        )]
+        fn large_divisors(
+            #[case] equation: &str,
+            #[case] operations: &[Operation],
+            #[case] solutions: u64,
+        ) {
+            let equation = equation.parse::<Equation>().unwrap();
+            assert_eq!(
+                equation.count_solutions(operations, Order::LeftToRight),
+                solutions
+            );
+            assert_eq!(
+                equation.solvable(operations, Order::LeftToRight),
+                solutions > 0
+            );
+            assert_eq!(
+                equation.brute_force(operations, Order::LeftToRight),
+                solutions > 0
+            );
+        }
+
+        #[test]
+        fn operator_display() {
+            let symbols = ALL.iter().map(ToString::to_string).collect::<Vec<_>>();
+            assert_eq!(symbols, ["+", "*", "||", "-", "^", "**", "/"]);
+        }
+
+        #[test]
+        fn config() {
+            let config =
+                toml::from_str::<SolverConfig>(r#"operators = ["add", "sub", "pow"]"#).unwrap();
+            assert_eq!(
+                config.operators,
+                [Operation::Add, Operation::Sub, Operation::Pow]
+            );
+            assert!(config.validate().is_ok());
+
+            let config = toml::from_str::<SolverConfig>(r#"operators = ["xor", "xor"]"#).unwrap();
+            assert!(matches!(config.validate(), Err(Error::InvalidConfig(_))));
+            assert!(toml::from_str::<SolverConfig>(r#"operators = ["modulo"]"#).is_err());
         }
     }
 }
@@ -1,5 +1,7 @@
 use aoclib::{config::Config, website::get_input};
-use day07::{part1, part2};
+use day07::{
+    calibrate, part1, part2, witnesses, ConcatPrecedence, Operation, Precision, SolverConfig,
+};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +24,40 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// load solver settings from this TOML file
+    ///
+    /// flags below override values from the file
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// also compute the calibration when these operators may appear between values
+    #[arg(long, value_enum, value_delimiter = ',')]
+    operators: Vec<Operation>,
+
+    /// how to represent values while evaluating equations
+    #[arg(long, value_enum)]
+    precision: Option<Precision>,
+
+    /// evaluate by operator precedence, `*` before `+`, instead of strictly left to right
+    ///
+    /// calibration totals are followed by their left-to-right equivalents for comparison
+    #[arg(long)]
+    standard_precedence: bool,
+
+    /// how tightly concatenation binds when evaluating by operator precedence
+    #[arg(long, value_enum)]
+    concat_precedence: Option<ConcatPrecedence>,
+
+    /// print an expression which makes each solvable equation true
+    ///
+    /// uses the configured operators, or those of part 2 if none are set
+    #[arg(long)]
+    witness: bool,
+
+    /// with each witness, print how many combinations of operators make the equation true
+    #[arg(long, requires = "witness")]
+    count: bool,
 }
 
 impl RunArgs {
@@ -37,18 +73,62 @@ impl RunArgs {
             Some(ref path) => Ok(path.clone()),
         }
     }
+
+    fn config(&self) -> Result<SolverConfig> {
+        let mut config = match self.config {
+            None => SolverConfig::default(),
+            Some(ref path) => SolverConfig::load(path)?,
+        };
+        if !self.operators.is_empty() {
+            config.operators = self.operators.clone();
+        }
+        if let Some(precision) = self.precision {
+            config.precision = precision;
+        }
+        if self.standard_precedence {
+            config.standard_precedence = true;
+        }
+        if let Some(concat_precedence) = self.concat_precedence {
+            config.concat_precedence = concat_precedence;
+        }
+        config.validate()?;
+        Ok(config)
+    }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::parse();
     let input_path = args.input()?;
+    let config = args.config()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, config.precision, config.order())?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, config.precision, config.order())?;
+    }
+    if !config.operators.is_empty() {
+        calibrate(
+            &input_path,
+            &config.operators,
+            config.precision,
+            config.order(),
+        )?;
+    }
+    if args.witness {
+        let operators = if config.operators.is_empty() {
+            Operation::PART2
+        } else {
+            &config.operators
+        };
+        witnesses(
+            &input_path,
+            operators,
+            args.count,
+            config.precision,
+            config.order(),
+        )?;
     }
     Ok(())
 }